@@ -0,0 +1,17 @@
+use std::{env, fs, path::Path};
+
+/// Copies the strategy selected with the `SCREEPS_STRATEGY` environment variable into the build
+/// output so that it can be embedded with `include_str!`. Defaults to `strategies/default.json`.
+fn main() {
+    let strategy = env::var("SCREEPS_STRATEGY").unwrap_or_else(|_| "default".to_owned());
+    let source = Path::new("strategies").join(format!("{strategy}.json"));
+    let out_dir = env::var("OUT_DIR").expect("cargo always sets OUT_DIR for build scripts");
+    let destination = Path::new(&out_dir).join("strategy.json");
+
+    println!("cargo:rerun-if-env-changed=SCREEPS_STRATEGY");
+    println!("cargo:rerun-if-changed={}", source.display());
+
+    if let Err(error) = fs::copy(&source, &destination) {
+        panic!("could not copy strategy {}: {error}", source.display());
+    }
+}
@@ -0,0 +1,245 @@
+use std::cell::{Cell, RefCell};
+
+use eyre::Result;
+use js_sys::{Array, Object, Reflect};
+use log::{debug, warn};
+use screeps_arena::game;
+use wasm_bindgen::{prelude::*, JsValue};
+
+use crate::{
+    global::stats::{self, MATCH_TICK_LIMIT},
+    logging,
+};
+
+/// The arena has a 50ms cpu limit per tick, warn when we get close to it.
+const CPU_TIME_WARNING_NANOSECONDS: f64 = 40_000_000.0;
+
+#[wasm_bindgen(module = "game/utils")]
+extern "C" {
+    /// Every object in the game, regardless of which arena features were compiled in.
+    #[wasm_bindgen(js_name = getObjects)]
+    fn get_all_objects() -> Array;
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ArenaKind {
+    CaptureTheFlag,
+    SpawnAndSwamp,
+    CollectAndControl,
+}
+
+impl ArenaKind {
+    /// Works out which arena we are playing from the objects on the map. Collect and control also
+    /// has spawns and containers, so score collectors are checked first.
+    pub fn detect() -> Option<Self> {
+        let mut has_flag = false;
+        let mut has_tower = false;
+        let mut has_container = false;
+        let mut has_enemy_spawn = false;
+        let mut has_score_collector = false;
+
+        for object in get_all_objects().iter() {
+            let object = Object::from(object);
+            match get_constructor_name(&object).as_deref() {
+                Some("Flag") => has_flag = true,
+                Some("StructureTower") => has_tower = true,
+                Some("StructureContainer") => has_container = true,
+                Some("StructureSpawn") => has_enemy_spawn |= !is_mine(&object),
+                Some("ScoreCollector") => has_score_collector = true,
+                _ => (),
+            }
+        }
+
+        if has_score_collector {
+            Some(Self::CollectAndControl)
+        } else if has_flag && has_tower {
+            Some(Self::CaptureTheFlag)
+        } else if has_container && has_enemy_spawn {
+            Some(Self::SpawnAndSwamp)
+        } else {
+            None
+        }
+    }
+}
+
+fn get_constructor_name(object: &Object) -> Option<String> {
+    let constructor = Reflect::get(object, &JsValue::from_str("constructor")).ok()?;
+    Reflect::get(&constructor, &JsValue::from_str("name"))
+        .ok()?
+        .as_string()
+}
+
+fn is_mine(object: &Object) -> bool {
+    Reflect::get(object, &JsValue::from_str("my"))
+        .ok()
+        .and_then(|my| my.as_bool())
+        .unwrap_or_default()
+}
+
+/// A bot for a single tutorial or arena. The dispatcher keeps the bot alive for the whole match,
+/// so anything that needs to persist between ticks can be stored on the implementing type.
+pub trait Arena {
+    fn name(&self) -> &'static str;
+
+    /// The arena this bot plays. Tutorials return `None` and always run.
+    fn kind(&self) -> Option<ArenaKind> {
+        None
+    }
+
+    /// Called once when the bot is selected, before the first `tick`.
+    fn init(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn tick(&mut self, tick: u32) -> Result<()>;
+
+    fn on_error(&mut self, error: eyre::Error) {
+        let error_message = format!("{:?}", error);
+        warn!("{}: {error_message}", self.name());
+    }
+
+    /// Called once on the first tick at or past `MATCH_TICK_LIMIT`, after the final stats for the
+    /// tick were recorded.
+    fn on_match_end(&mut self, _tick: u32) {}
+}
+
+thread_local! {
+    static ARENAS: RefCell<Option<Vec<Box<dyn Arena>>>> = RefCell::new(None);
+    static MATCH_ENDED: Cell<bool> = Cell::new(false);
+}
+
+/// Runs the bots that match the arena we are playing for this tick. The bots are selected the
+/// first time this is called.
+pub fn dispatch(tick: u32) {
+    if tick == 1 {
+        logging::setup_logging(logging::Info);
+    }
+
+    ARENAS.with(|arenas| {
+        let mut arenas = arenas.borrow_mut();
+        let arenas = arenas.get_or_insert_with(select_arenas);
+
+        for arena in arenas.iter_mut() {
+            run_arena(arena.as_mut(), tick);
+        }
+    });
+
+    stats::update(tick, cfg!(feature = "periodic-stats"));
+
+    if tick >= MATCH_TICK_LIMIT && !MATCH_ENDED.with(|ended| ended.replace(true)) {
+        end_match(tick);
+    }
+}
+
+/// Lets every bot know the match is over, then prints the final stats report.
+fn end_match(tick: u32) {
+    ARENAS.with(|arenas| {
+        for arena in arenas.borrow_mut().iter_mut().flatten() {
+            arena.on_match_end(tick);
+        }
+    });
+
+    stats::report();
+}
+
+/// Keeps the tutorials and the compiled in bot for the detected arena, then initializes them. If
+/// the arena can't be detected every compiled in bot runs, like before detection existed.
+fn select_arenas() -> Vec<Box<dyn Arena>> {
+    let compiled_arenas = create_arenas();
+    let mut arenas = match ArenaKind::detect() {
+        Some(detected) => {
+            if !compiled_arenas
+                .iter()
+                .any(|arena| arena.kind() == Some(detected))
+            {
+                let compiled_names = compiled_arenas
+                    .iter()
+                    .map(|arena| arena.name())
+                    .collect::<Vec<&str>>();
+                warn!(
+                    "Detected arena {:?} but this build only contains: {:?}. Rebuild with the matching arena feature",
+                    detected, compiled_names
+                );
+            }
+
+            compiled_arenas
+                .into_iter()
+                .filter(|arena| arena.kind().map_or(true, |kind| kind == detected))
+                .collect::<Vec<Box<dyn Arena>>>()
+        }
+        None => {
+            warn!("Could not detect the arena, running every compiled in bot");
+            compiled_arenas
+        }
+    };
+
+    for arena in arenas.iter_mut() {
+        if let Err(error) = arena.init() {
+            arena.on_error(error);
+        }
+    }
+
+    arenas
+}
+
+fn run_arena(arena: &mut dyn Arena, tick: u32) {
+    let start = game::utils::get_cpu_time();
+
+    if let Err(error) = arena.tick(tick) {
+        arena.on_error(error);
+    }
+
+    let cpu_time = game::utils::get_cpu_time() - start;
+    if cpu_time > CPU_TIME_WARNING_NANOSECONDS {
+        warn!("{} used {cpu_time}ns of cpu on tick {tick}", arena.name());
+    } else {
+        debug!("{} used {cpu_time}ns of cpu on tick {tick}", arena.name());
+    }
+}
+
+#[allow(unused_mut)]
+fn create_arenas() -> Vec<Box<dyn Arena>> {
+    let mut arenas: Vec<Box<dyn Arena>> = vec![];
+
+    #[cfg(feature = "arena-tutorial-loop-and-import")]
+    arenas.push(Box::new(crate::tutorials::loop_and_import::LoopAndImport));
+
+    #[cfg(feature = "arena-tutorial-simple_move")]
+    arenas.push(Box::new(crate::tutorials::simple_move::SimpleMove));
+
+    #[cfg(feature = "arena-tutorial-first_attack")]
+    arenas.push(Box::new(crate::tutorials::first_attack::FirstAttack));
+
+    #[cfg(feature = "arena-tutorial-creeps_bodies")]
+    arenas.push(Box::new(crate::tutorials::creeps_bodies::CreepsBodies));
+
+    #[cfg(feature = "arena-tutorial-store-and-transfer")]
+    arenas.push(Box::new(
+        crate::tutorials::store_and_transfer::StoreAndTransfer::new(),
+    ));
+
+    #[cfg(feature = "arena-tutorial-terrain")]
+    arenas.push(Box::new(crate::tutorials::terrain::Terrain::new()));
+
+    #[cfg(feature = "arena-tutorial-spawn-creeps")]
+    arenas.push(Box::new(crate::tutorials::spawn_creeps::SpawnCreeps::new()));
+
+    #[cfg(feature = "arena-tutorial-harvest-energy")]
+    arenas.push(Box::new(
+        crate::tutorials::harvest_energy::HarvestEnergy::new(),
+    ));
+
+    #[cfg(feature = "arena-tutorial-construction")]
+    arenas.push(Box::new(crate::tutorials::construction::Construction));
+
+    #[cfg(feature = "arena-tutorial-final-test")]
+    arenas.push(Box::new(crate::tutorials::final_test::FinalTest::new()));
+
+    #[cfg(feature = "arena-capture-the-flag")]
+    arenas.push(Box::new(crate::ctf::CaptureTheFlag::new()));
+
+    #[cfg(feature = "arena-spawn-and-swamp")]
+    arenas.push(Box::new(crate::swamp::SpawnAndSwamp::new()));
+
+    arenas
+}
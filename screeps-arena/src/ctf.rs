@@ -1,23 +1,43 @@
-use js_sys::Array;
-use log::warn;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use eyre::Result;
+use js_sys::{Array, Object};
+use log::info;
 use screeps_arena::{
     game::utils::get_objects_by_prototype, prototypes, BodyPart, Creep, Flag,
-    OwnedStructureProperties, ReturnCode, StructureTower,
+    OwnedStructureProperties, Part, StructureTower,
 };
 
-use crate::global::{
-    role::Role,
-    utilities::{create_creeps_array, get_closest_creep, get_creeps, object_to_creep},
+use crate::{
+    arena::{Arena, ArenaKind},
+    global::{
+        classifier::{classify, pick_target, CombatRole},
+        commander::{execute_order, Commander, Order, World},
+        debounce::{Debounced, Threshold},
+        healing::{execute_heal, HealPlan},
+        influence::InfluenceMap,
+        pairing::{run_bodyguard, Pairings},
+        phase::{Phase, PhaseManager, PhaseSignals},
+        ranged::fire_ranged,
+        retreat::{get_rally_point, retreat_to, should_retreat},
+        role::{attach_role, RoleBehavior, RoleRegistry},
+        stats,
+        strategy::get_strategy,
+        stuck::{MoveStatus, StuckDetector},
+        tower::TowerController,
+        tracker::EnemyTracker,
+        traffic::{TrafficManager, COMBAT_PRIORITY, HEALER_PRIORITY},
+        utilities::{create_position_object, creep_to_array, get_creeps, log_creep_error},
+    },
 };
 
-#[derive(PartialEq)]
-enum CreepType {
-    Fighter,
-    Ranger,
-    Healer,
-}
+const DEFENDER: &str = "defender";
+const ATTACKER: &str = "attacker";
+const HEALER: &str = "healer";
+/// How far ahead enemy positions are predicted when judging how close the enemy is to our flag.
+const PREDICTION_TICKS: u32 = 3;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 enum EnemyState {
     EnemySide,
     River,
@@ -27,15 +47,39 @@ enum EnemyState {
 }
 
 impl EnemyState {
-    pub fn new(enemies: &Vec<Creep>, distance_to_our_flag: u8, tick: u32) -> Self {
+    /// The enemy has to come `state_margin` tiles further back out of a state than it had to
+    /// go in, so an enemy moving back and forth on the border doesn't change the state.
+    pub fn new(
+        enemies: &Vec<Creep>,
+        distance_to_our_flag: u8,
+        phase: Phase,
+        previous: EnemyState,
+    ) -> Self {
+        let strategy = &get_strategy().capture_the_flag;
+        let our_side = Threshold::new(
+            strategy.our_side_distance,
+            strategy
+                .our_side_distance
+                .saturating_add(strategy.state_margin),
+        );
+        let river = Threshold::new(
+            strategy.river_distance,
+            strategy
+                .river_distance
+                .saturating_add(strategy.state_margin),
+        );
+
         if enemies.len() <= 1 {
             Self::MostlyDead
-        } else if tick >= 1500 {
+        } else if phase == Phase::Endgame {
             Self::Turtling
         } else {
-            if distance_to_our_flag < 5 {
+            if our_side.is_below(distance_to_our_flag, previous == Self::OurSide) {
                 Self::OurSide
-            } else if distance_to_our_flag < 75 {
+            } else if river.is_below(
+                distance_to_our_flag,
+                matches!(previous, Self::OurSide | Self::River),
+            ) {
                 Self::River
             } else {
                 Self::EnemySide
@@ -49,6 +93,7 @@ impl EnemyState {
 /// ## Turrets
 ///
 /// - [x] attack all enemies within the swamp near our flag
+/// - [x] pick targets by kill potential and threat, heal our creeps when no shot is worth it
 ///
 /// ## Creeps
 ///
@@ -63,82 +108,355 @@ impl EnemyState {
 /// - return to middle of our side of map
 /// - if the opponents are moving onto our side
 ///     - attack the nearest enemy
+///     - fall back to the rally point when badly hurt, come back once healed
 ///
 /// - When not healing, healers stay near the flag
-/// - At 1500 ticks, if no enemies on our side, take the opponents flag
+/// - Once we are fighting, healers stay behind the attackers they are paired with
+/// - In the endgame, which starts at the strategy's turtle tick (1500 by default), if no enemies
+///   on our side, take the opponents flag
 ///
 /// ## All
 ///
 /// Once all enemies are dead, swarm the flag
-pub fn run(tick: u32) {
-    #[cfg(feature = "arena-capture-the-flag")]
-    {
-        let my_creeps = get_creeps(true);
-        let my_flag = get_flag(true);
-        let enemies = get_creeps(false);
-        let my_towers = get_towers(true);
-        let enemy_flag = get_flag(false);
-
-        if tick == 1 {
-            let mut assigned_defender = false;
-
-            for creep in &my_creeps {
-                match determine_creep_type(creep) {
-                    CreepType::Healer => {
-                        if !assigned_defender {
-                            Role::Defender.attach_to_creep(creep);
-                            assigned_defender = true;
-                        } else {
-                            Role::Healer.attach_to_creep(creep);
-                        }
+pub fn run(tick: u32, roles: &RoleRegistry<Context>, state: &mut MatchState) -> Result<()> {
+    let MatchState {
+        enemy_tracker,
+        stuck_detector,
+        pairings,
+        commander,
+        phases,
+        enemy_state,
+    } = state;
+    let my_creeps = get_creeps(true);
+    let my_flag = get_flag(true)?;
+    let enemies = get_creeps(false);
+    let my_towers = get_towers(true);
+    let enemy_flag = get_flag(false)?;
+    enemy_tracker.borrow_mut().update(tick, &enemies);
+    stuck_detector.borrow_mut().forget_dead(&my_creeps);
+
+    if tick == 1 {
+        let mut assigned_defender = false;
+
+        for creep in &my_creeps {
+            let result = match classify(creep).primary {
+                CombatRole::Healer => {
+                    if !assigned_defender {
+                        assigned_defender = true;
+                        attach_role(creep, DEFENDER)
+                    } else {
+                        attach_role(creep, HEALER)
                     }
-                    _ => Role::Attacker.attach_to_creep(creep),
                 }
-            }
+                _ => attach_role(creep, ATTACKER),
+            };
+            log_creep_error(creep, result);
         }
+    }
 
-        if let Some(closest_enemy) = my_flag.find_closest_by_range(&create_creeps_array(&enemies)) {
-            let enemy_distance_to_flag = my_flag.get_range_to(&closest_enemy);
-            let closest_enemy_creep = object_to_creep(&closest_enemy).unwrap();
-            let enemy_state = EnemyState::new(&enemies, enemy_distance_to_flag, tick);
-
-            for creep in &my_creeps {
-                let role = Role::from(creep);
-
-                match role {
-                    Role::Defender => run_defender(creep, &my_flag),
-                    Role::Attacker | Role::Healer => run_attacker(
-                        creep,
-                        &my_flag,
-                        &my_creeps,
-                        enemy_state,
-                        &enemies,
-                        &enemy_flag,
-                    ),
-                    // Role::Healer => run_healer(creep, &my_creeps, &my_flag),
-                }
+    if let Some(closest_enemy) = my_flag.find_closest_by_range(&creep_to_array(&enemies)) {
+        // an enemy charging the flag counts as already being where it is headed
+        let enemy_distance_to_flag = enemy_tracker
+            .borrow()
+            .closest_approach((my_flag.x(), my_flag.y()), PREDICTION_TICKS)
+            .map_or(my_flag.get_range_to(&closest_enemy), |predicted| {
+                predicted.min(my_flag.get_range_to(&closest_enemy))
+            });
+        let phase = phases.update(&PhaseSignals {
+            tick,
+            army_strength: get_strength(&my_creeps),
+            enemy_strength: get_strength(&enemies),
+            // there is no energy to collect here, the phases only follow time and threat
+            energy_collected: 0,
+            enemies_near_home: enemy_distance_to_flag
+                < get_strategy().capture_the_flag.our_side_distance,
+        });
+        let wanted_state =
+            EnemyState::new(&enemies, enemy_distance_to_flag, phase, enemy_state.state());
+        let enemy_state = enemy_state.update(wanted_state);
+
+        #[cfg(feature = "debug-visuals")]
+        {
+            use crate::global::visuals;
+
+            visuals::draw_text(
+                &format!("{:?} {:?}", phase, enemy_state),
+                my_flag.x(),
+                my_flag.y(),
+            );
+            visuals::draw_threat_range(
+                my_flag.x(),
+                my_flag.y(),
+                get_strategy().capture_the_flag.tower_range,
+            );
+            for enemy in &enemies {
+                visuals::draw_threat_range(enemy.x(), enemy.y(), 3);
             }
-            run_towers(&my_towers, &closest_enemy_creep, enemy_distance_to_flag);
-        } else {
-            for creep in &my_creeps {
-                creep.move_to(&enemy_flag, None);
+            for record in enemy_tracker
+                .borrow()
+                .advancing_toward((my_flag.x(), my_flag.y()))
+            {
+                let (x, y) = record.predict_position(PREDICTION_TICKS);
+                visuals::draw_text("!", x, y);
+            }
+        }
+
+        #[cfg(feature = "debug-visuals")]
+        for creep in &my_creeps {
+            if let Ok(Some(role)) = roles.role_of(creep) {
+                crate::global::visuals::draw_role_label(creep, role.name());
             }
         }
+
+        let military = my_creeps
+            .iter()
+            .filter(|creep| roles.is_role(creep, ATTACKER))
+            .cloned()
+            .collect::<Vec<Creep>>();
+        let healers = my_creeps
+            .iter()
+            .filter(|creep| roles.is_role(creep, HEALER))
+            .cloned()
+            .collect::<Vec<Creep>>();
+        pairings.borrow_mut().update(&healers, &military);
+
+        // a squad holds our flag until the enemy is mostly dead, then it takes theirs
+        let flag = match enemy_state {
+            EnemyState::MostlyDead => &enemy_flag,
+            _ => &my_flag,
+        };
+        let orders = commander.tick(&World {
+            military,
+            enemies: enemies.clone(),
+            home: (my_flag.x(), my_flag.y()),
+            enemy_home: Some((enemy_flag.x(), enemy_flag.y())),
+            flag: Some((flag.x(), flag.y())),
+            phase,
+        });
+
+        let influence = InfluenceMap::new(&my_creeps, &enemies, &my_towers, &get_towers(false));
+        let rally_point = get_rally_point(
+            (my_flag.x(), my_flag.y()),
+            (enemy_flag.x(), enemy_flag.y()),
+            &influence,
+        );
+
+        let context = Context {
+            heal_plan: HealPlan::new(&healers, &my_creeps, &enemies),
+            enemy_tracker: enemy_tracker.clone(),
+            influence,
+            rally_point,
+            stuck_detector: stuck_detector.clone(),
+            pairings: pairings.clone(),
+            my_flag,
+            my_creeps,
+            enemy_state,
+            enemies,
+            enemy_flag,
+            orders,
+            traffic: RefCell::new(TrafficManager::new()),
+        };
+        roles.run_creeps(&context.my_creeps, &context);
+        TowerController::new(
+            (context.my_flag.x(), context.my_flag.y()),
+            get_strategy().capture_the_flag.tower_range,
+        )
+        .run(&my_towers, &context.my_creeps, &context.enemies);
+        context.traffic.into_inner().resolve();
+    } else {
+        let mut traffic = TrafficManager::new();
+        for creep in &my_creeps {
+            let result = traffic.request_move_toward(creep, &enemy_flag, None, COMBAT_PRIORITY);
+            log_creep_error(creep, result);
+        }
+        traffic.resolve();
     }
+
+    Ok(())
+}
+
+pub struct Context {
+    heal_plan: HealPlan,
+    enemy_tracker: Rc<RefCell<EnemyTracker>>,
+    influence: InfluenceMap,
+    /// Where attackers that have to fall back gather.
+    rally_point: (u8, u8),
+    stuck_detector: Rc<RefCell<StuckDetector>>,
+    pairings: Rc<RefCell<Pairings>>,
+    my_flag: Flag,
+    my_creeps: Vec<Creep>,
+    enemy_state: EnemyState,
+    enemies: Vec<Creep>,
+    enemy_flag: Flag,
+    /// What the commander wants every attacker to do this tick, by creep id.
+    orders: HashMap<String, Order>,
+    /// Roles submit their moves here, resolve it once every creep has run.
+    traffic: RefCell<TrafficManager>,
 }
 
-fn determine_creep_type(creep: &Creep) -> CreepType {
-    for body_part in creep.body() {
-        match body_part.part() {
-            screeps_arena::Part::Attack => return CreepType::Fighter,
-            screeps_arena::Part::RangedAttack => return CreepType::Ranger,
-            screeps_arena::Part::Heal => return CreepType::Healer,
-            _ => continue,
+/// Everything the bot remembers between ticks of a match.
+pub struct MatchState {
+    enemy_tracker: Rc<RefCell<EnemyTracker>>,
+    stuck_detector: Rc<RefCell<StuckDetector>>,
+    pairings: Rc<RefCell<Pairings>>,
+    commander: Commander,
+    phases: PhaseManager,
+    enemy_state: Debounced<EnemyState>,
+}
+
+impl MatchState {
+    fn new() -> Self {
+        let strategy = &get_strategy().capture_the_flag;
+
+        Self {
+            enemy_tracker: Rc::new(RefCell::new(EnemyTracker::new())),
+            stuck_detector: Rc::new(RefCell::new(StuckDetector::new())),
+            pairings: Rc::new(RefCell::new(Pairings::new())),
+            commander: Commander::new(),
+            enemy_state: Debounced::new(
+                EnemyState::EnemySide,
+                strategy.state_confirm_ticks,
+                strategy.state_min_dwell_ticks,
+            ),
+            phases: PhaseManager::new(strategy.turtle_tick).on_change(|from, to, tick| {
+                info!("phase changed from {:?} to {:?} at tick {}", from, to, tick);
+                stats::record_phase(&format!("{:?}", to), tick);
+            }),
+        }
+    }
+}
+
+pub struct CaptureTheFlag {
+    roles: RoleRegistry<Context>,
+    state: MatchState,
+}
+
+impl CaptureTheFlag {
+    pub fn new() -> Self {
+        let roles = RoleRegistry::new()
+            .register(DefenderRole)
+            .register(AttackerRole)
+            .register(HealerRole);
+
+        Self {
+            roles,
+            state: MatchState::new(),
         }
     }
+}
+
+impl Arena for CaptureTheFlag {
+    fn name(&self) -> &'static str {
+        "capture the flag"
+    }
+
+    fn kind(&self) -> Option<ArenaKind> {
+        Some(ArenaKind::CaptureTheFlag)
+    }
+
+    fn tick(&mut self, tick: u32) -> Result<()> {
+        run(tick, &self.roles, &mut self.state)
+    }
+}
+
+struct DefenderRole;
+
+impl RoleBehavior for DefenderRole {
+    type Context = Context;
+
+    fn name(&self) -> &'static str {
+        DEFENDER
+    }
+
+    fn body(&self) -> Vec<Part> {
+        vec![
+            Part::Attack,
+            Part::Attack,
+            Part::Attack,
+            Part::RangedAttack,
+            Part::RangedAttack,
+            Part::RangedAttack,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+        ]
+    }
 
-    warn!("Could not determine creep type");
-    panic!();
+    fn run(&self, creep: &Creep, context: &Context) -> Result<()> {
+        run_defender(creep, context)
+    }
+
+    fn spawn_priority(&self) -> u8 {
+        0
+    }
+}
+
+struct AttackerRole;
+
+impl RoleBehavior for AttackerRole {
+    type Context = Context;
+
+    fn name(&self) -> &'static str {
+        ATTACKER
+    }
+
+    fn body(&self) -> Vec<Part> {
+        vec![
+            Part::Attack,
+            Part::Attack,
+            Part::RangedAttack,
+            Part::RangedAttack,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+        ]
+    }
+
+    fn run(&self, creep: &Creep, context: &Context) -> Result<()> {
+        run_attacker(creep, false, context)
+    }
+
+    fn spawn_priority(&self) -> u8 {
+        0
+    }
+}
+
+struct HealerRole;
+
+impl RoleBehavior for HealerRole {
+    type Context = Context;
+
+    fn name(&self) -> &'static str {
+        HEALER
+    }
+
+    fn body(&self) -> Vec<Part> {
+        vec![
+            Part::Heal,
+            Part::Heal,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+        ]
+    }
+
+    fn run(&self, creep: &Creep, context: &Context) -> Result<()> {
+        run_attacker(creep, true, context)
+    }
+
+    fn spawn_priority(&self) -> u8 {
+        0
+    }
 }
 
 /// If there are any enemies on our side of the map
@@ -149,72 +467,152 @@ fn determine_creep_type(creep: &Creep) -> CreepType {
 /// - ranged attack
 ///
 /// Otherwise collect body parts
-fn run_defender(creep: &Creep, flag: &Flag) {
-    creep.move_to(flag, None);
+fn run_defender(creep: &Creep, context: &Context) -> Result<()> {
+    let status = context.stuck_detector.borrow_mut().move_to(
+        creep,
+        &context.my_flag,
+        None,
+        &mut context.traffic.borrow_mut(),
+        COMBAT_PRIORITY,
+    )?;
+    // can't get to the flag, defend from where we are instead
+    if status == MoveStatus::Stuck {
+        if let Some(target) = pick_target(creep, &context.enemies) {
+            fire_ranged(creep, Some(target), &context.enemies);
+            creep.attack(target);
+        }
+    }
     creep.heal(creep);
+
+    Ok(())
 }
 
-fn run_attacker(
-    creep: &Creep,
-    my_flag: &Flag,
-    my_creeps: &Vec<Creep>,
-    enemy_state: EnemyState,
-    enemy_creeps: &Vec<Creep>,
-    enemy_flag: &Flag,
-) {
-    let hurt_creeps = get_hurt_creeps(my_creeps);
-    if !hurt_creeps.is_empty() && Role::from(creep) == Role::Healer {
-        if let Some(closest_hurt_creep) = get_closest_creep(creep, &hurt_creeps) {
-            if creep.heal(&closest_hurt_creep) == ReturnCode::NotInRange {
-                creep.ranged_heal(&closest_hurt_creep);
-                creep.move_to(&closest_hurt_creep, None);
-            }
-            return;
+fn run_attacker(creep: &Creep, is_healer: bool, context: &Context) -> Result<()> {
+    let my_flag = &context.my_flag;
+    let enemy_creeps = &context.enemies;
+    let enemy_flag = &context.enemy_flag;
+    let mut traffic = context.traffic.borrow_mut();
+    let priority = if is_healer {
+        HEALER_PRIORITY
+    } else {
+        COMBAT_PRIORITY
+    };
+
+    if is_healer {
+        if let Some(order) = context.heal_plan.order_for(creep) {
+            return execute_heal(creep, order, &mut traffic);
+        }
+
+        let is_fighting = matches!(
+            context.enemy_state,
+            EnemyState::OurSide | EnemyState::Turtling | EnemyState::MostlyDead
+        );
+        let pairings = context.pairings.borrow();
+        let partners = pairings.partners(creep, &context.my_creeps);
+        if is_fighting && !partners.is_empty() {
+            return run_bodyguard(creep, &partners, enemy_creeps, &mut traffic);
         }
     }
 
-    match enemy_state {
-        EnemyState::EnemySide => {
+    let order = creep
+        .id()
+        .as_string()
+        .and_then(|id| context.orders.get(&id));
+    match (context.enemy_state, order) {
+        // the squads report where they are needed, a squad that ran into the enemy fights
+        // instead of collecting
+        (EnemyState::EnemySide, Some(order @ Order::Attack { .. }))
+        | (EnemyState::River | EnemyState::MostlyDead, Some(order)) => {
+            execute_order(
+                creep,
+                order,
+                enemy_creeps,
+                &context.enemy_tracker.borrow(),
+                &mut traffic,
+            )?;
+        }
+        // while we aren't fighting, walk around anything that could hurt us
+        (EnemyState::EnemySide, _) => {
+            let safe_move_options = context.influence.move_options(creep.hits());
+            let mut stuck_detector = context.stuck_detector.borrow_mut();
             let body_parts = get_objects_by_prototype(prototypes::BODY_PART);
-            if body_parts.is_empty() {
-                creep.move_to(my_flag, None);
-            } else {
-                let body_parts_array = create_body_parts_array(&body_parts);
-                if let Some(closest_body_part) = creep.find_closest_by_path(&body_parts_array, None)
-                {
-                    creep.move_to(&closest_body_part, None);
-                }
+            let body_parts_array = create_body_parts_array(&body_parts);
+            let closest_body_part =
+                creep.find_closest_by_path(&body_parts_array, Some(&safe_move_options));
+            let target: &Object = match &closest_body_part {
+                Some(closest_body_part) => closest_body_part,
+                None => my_flag,
+            };
+            let status = stuck_detector.move_to(
+                creep,
+                target,
+                Some(&safe_move_options),
+                &mut traffic,
+                priority,
+            )?;
+            // give up on a body part we can't reach and wait at the flag instead
+            if status == MoveStatus::Stuck {
+                traffic.request_move_toward(creep, my_flag, Some(&safe_move_options), priority)?;
             }
         }
-        EnemyState::River => {
-            creep.move_to(my_flag, None);
+        (EnemyState::River, _) => {
+            let safe_move_options = context.influence.move_options(creep.hits());
+            let status = context.stuck_detector.borrow_mut().move_to(
+                creep,
+                my_flag,
+                Some(&safe_move_options),
+                &mut traffic,
+                priority,
+            )?;
+            // wait at the rally point when the way to the flag is blocked
+            if status == MoveStatus::Stuck {
+                let (x, y) = context.rally_point;
+                traffic.request_move_toward(
+                    creep,
+                    &create_position_object(x, y),
+                    Some(&safe_move_options),
+                    priority,
+                )?;
+            }
         }
-        EnemyState::OurSide | EnemyState::Turtling => {
-            if let Some(closest_enemy) = get_closest_creep(creep, enemy_creeps) {
-                creep.ranged_attack(&closest_enemy);
-                creep.attack(&closest_enemy);
-                creep.move_to(&closest_enemy, None);
+        (EnemyState::OurSide | EnemyState::Turtling, _) => {
+            if !is_healer && should_retreat(creep, enemy_creeps)? {
+                return retreat_to(creep, context.rally_point, &context.influence, &mut traffic);
+            }
+
+            let target_order = pick_target(creep, enemy_creeps).map(|target| Order::Attack {
+                target: target.clone(),
+            });
+            match order.or(target_order.as_ref()) {
+                Some(order) => execute_order(
+                    creep,
+                    order,
+                    enemy_creeps,
+                    &context.enemy_tracker.borrow(),
+                    &mut traffic,
+                )?,
+                None => traffic.idle(creep),
             }
         }
-        EnemyState::MostlyDead => {
+        (EnemyState::MostlyDead, _) => {
             if let Some(enemy) = enemy_creeps.first() {
-                creep.ranged_attack(enemy);
+                fire_ranged(creep, Some(enemy), enemy_creeps);
                 creep.attack(enemy);
-                creep.move_to(enemy, None);
+                traffic.request_move_toward(creep, enemy, None, priority)?;
             } else {
-                creep.move_to(&enemy_flag, None);
+                traffic.request_move_toward(creep, enemy_flag, None, priority)?;
             }
         }
     }
+
+    Ok(())
 }
 
-fn get_flag(my: bool) -> Flag {
-    let flags = get_objects_by_prototype(prototypes::FLAG)
+fn get_flag(my: bool) -> Result<Flag> {
+    get_objects_by_prototype(prototypes::FLAG)
         .into_iter()
-        .filter(|flag| flag.my().unwrap_or_default() == my)
-        .collect::<Vec<Flag>>();
-
-    flags[0].clone()
+        .find(|flag| flag.my().unwrap_or_default() == my)
+        .ok_or(eyre::eyre!("Could not find flag, my: {my}"))
 }
 
 fn get_towers(my: bool) -> Vec<StructureTower> {
@@ -224,20 +622,12 @@ fn get_towers(my: bool) -> Vec<StructureTower> {
         .collect()
 }
 
-fn run_towers(towers: &Vec<StructureTower>, closest_enemy: &Creep, enemy_distance: u8) {
-    if enemy_distance <= 5 {
-        for tower in towers {
-            tower.attack(closest_enemy);
-        }
-    }
-}
-
-fn get_hurt_creeps(creeps: &Vec<Creep>) -> Vec<Creep> {
+/// Sum of the threat scores of the creeps.
+fn get_strength(creeps: &[Creep]) -> u32 {
     creeps
-        .clone()
-        .into_iter()
-        .filter(|creep| creep.hits_max() > creep.hits())
-        .collect()
+        .iter()
+        .map(|creep| classify(creep).threat.score())
+        .sum()
 }
 
 fn create_body_parts_array(body_parts: &Vec<BodyPart>) -> Array {
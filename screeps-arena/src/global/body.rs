@@ -0,0 +1,217 @@
+use screeps_arena::Creep;
+
+use super::{
+    classifier::{
+        PartCounts, ATTACK_POWER, HEAL_POWER, RANGED_ATTACK_POWER, RANGED_HEAL_POWER,
+        RANGED_MASS_ATTACK_POWER,
+    },
+    terrain::Terrain,
+};
+
+/// Energy a single carry part holds.
+pub const CARRY_CAPACITY: u32 = 50;
+/// Fatigue a single move part removes every tick.
+pub const MOVE_POWER: u32 = 2;
+
+/// What a creep can do right now, worked out from the parts that still have hits. Works the same
+/// for our creeps and the enemy's.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct CreepStats {
+    pub parts: PartCounts,
+    pub melee_dps: u32,
+    /// Single target ranged damage at range 1, 2 and 3.
+    pub ranged_dps: [u32; 3],
+    /// Ranged mass attack damage to each creep at range 1, 2 and 3.
+    pub mass_attack_damage: [u32; 3],
+    pub heal_per_tick: u32,
+    pub ranged_heal_per_tick: u32,
+    pub carry_capacity: u32,
+    /// Parts that make the creep tired when it moves. Destroyed parts still weigh the creep down,
+    /// carry parts only once something is in them.
+    pub weight: u32,
+    pub fatigue: u32,
+}
+
+impl CreepStats {
+    pub fn from_creep(creep: &Creep) -> Self {
+        let parts = PartCounts::from_creep(creep);
+        let carried = creep.store().get_used_capacity(None);
+        let loaded_carries = ((carried + CARRY_CAPACITY - 1) / CARRY_CAPACITY).min(parts.carries);
+        let weight = PartCounts::spawned_with(creep).weight() + loaded_carries;
+
+        Self {
+            weight,
+            fatigue: creep.fatigue(),
+            ..Self::from_parts(parts)
+        }
+    }
+
+    /// Stats of a body that isn't carrying anything, isn't tired and has no destroyed parts, like
+    /// one we are about to spawn.
+    pub fn from_parts(parts: PartCounts) -> Self {
+        let ranged_damage = parts.ranged_attacks * RANGED_ATTACK_POWER;
+
+        Self {
+            parts,
+            melee_dps: parts.attacks * ATTACK_POWER,
+            ranged_dps: [ranged_damage; 3],
+            mass_attack_damage: RANGED_MASS_ATTACK_POWER.map(|power| parts.ranged_attacks * power),
+            heal_per_tick: parts.heals * HEAL_POWER,
+            ranged_heal_per_tick: parts.heals * RANGED_HEAL_POWER,
+            carry_capacity: parts.carries * CARRY_CAPACITY,
+            weight: parts.weight(),
+            fatigue: 0,
+        }
+    }
+
+    /// Ranged damage to a single target at the range, 0 when it is out of reach.
+    pub fn ranged_damage_at_range(&self, range: u8) -> u32 {
+        match range {
+            0 => self.ranged_dps[0],
+            1..=3 => self.ranged_dps[range as usize - 1],
+            _ => 0,
+        }
+    }
+
+    /// Ranged mass attack damage to a creep at the range, 0 when it is out of reach.
+    pub fn mass_attack_damage_at_range(&self, range: u8) -> u32 {
+        match range {
+            0 => self.mass_attack_damage[0],
+            1..=3 => self.mass_attack_damage[range as usize - 1],
+            _ => 0,
+        }
+    }
+
+    /// Healing the creep can do on a target at the range, 0 when it is out of reach.
+    pub fn heal_at_range(&self, range: u8) -> u32 {
+        match range {
+            0 | 1 => self.heal_per_tick,
+            2 | 3 => self.ranged_heal_per_tick,
+            _ => 0,
+        }
+    }
+
+    /// Fatigue the creep gets from stepping onto the terrain. None for walls.
+    pub fn fatigue_per_step(&self, terrain: Terrain) -> Option<u32> {
+        Some(self.weight * terrain.fatigue_per_part()?)
+    }
+
+    /// Ticks a step onto the terrain costs once the creep is moving, at least 1. None when the
+    /// creep can't move there at all.
+    pub fn ticks_per_step(&self, terrain: Terrain) -> Option<u32> {
+        let fatigue = self.fatigue_per_step(terrain)?;
+        let recovery = self.parts.moves * MOVE_POWER;
+        if recovery == 0 {
+            return None;
+        }
+
+        Some(((fatigue + recovery - 1) / recovery).max(1))
+    }
+
+    /// Ticks until the creep has shaken off its current fatigue and can move again, 0 when it
+    /// can move this tick.
+    pub fn ticks_until_move(&self) -> Option<u32> {
+        if self.fatigue == 0 {
+            return Some(0);
+        }
+
+        let recovery = self.parts.moves * MOVE_POWER;
+        if recovery == 0 {
+            return None;
+        }
+
+        Some((self.fatigue + recovery - 1) / recovery)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_parts_adds_up_the_parts() {
+        let stats = CreepStats::from_parts(PartCounts {
+            moves: 2,
+            carries: 1,
+            attacks: 1,
+            ranged_attacks: 2,
+            heals: 1,
+            ..PartCounts::default()
+        });
+
+        assert_eq!(stats.melee_dps, 30);
+        assert_eq!(stats.ranged_dps, [20, 20, 20]);
+        assert_eq!(stats.mass_attack_damage, [20, 8, 2]);
+        assert_eq!(stats.heal_per_tick, 12);
+        assert_eq!(stats.ranged_heal_per_tick, 4);
+        assert_eq!(stats.carry_capacity, 50);
+        // move and empty carry parts weigh nothing
+        assert_eq!(stats.weight, 4);
+    }
+
+    #[test]
+    fn damage_and_healing_stop_past_range_3() {
+        let stats = CreepStats::from_parts(PartCounts {
+            ranged_attacks: 1,
+            heals: 1,
+            ..PartCounts::default()
+        });
+
+        assert_eq!(stats.ranged_damage_at_range(0), 10);
+        assert_eq!(stats.ranged_damage_at_range(3), 10);
+        assert_eq!(stats.ranged_damage_at_range(4), 0);
+        assert_eq!(stats.mass_attack_damage_at_range(2), 4);
+        assert_eq!(stats.mass_attack_damage_at_range(4), 0);
+        assert_eq!(stats.heal_at_range(1), 12);
+        assert_eq!(stats.heal_at_range(3), 4);
+        assert_eq!(stats.heal_at_range(4), 0);
+    }
+
+    #[test]
+    fn ticks_per_step_depends_on_weight_and_moves() {
+        let stats = CreepStats::from_parts(PartCounts {
+            moves: 1,
+            attacks: 1,
+            ..PartCounts::default()
+        });
+
+        assert_eq!(stats.ticks_per_step(Terrain::Plain), Some(1));
+        assert_eq!(stats.ticks_per_step(Terrain::Swamp), Some(5));
+        assert_eq!(stats.ticks_per_step(Terrain::Wall), None);
+    }
+
+    #[test]
+    fn creeps_without_moves_never_move() {
+        let stats = CreepStats {
+            fatigue: 4,
+            ..CreepStats::from_parts(PartCounts {
+                attacks: 1,
+                ..PartCounts::default()
+            })
+        };
+
+        assert_eq!(stats.ticks_per_step(Terrain::Plain), None);
+        assert_eq!(stats.ticks_until_move(), None);
+    }
+
+    #[test]
+    fn fatigue_wears_off_at_the_move_power() {
+        let stats = CreepStats {
+            fatigue: 5,
+            ..CreepStats::from_parts(PartCounts {
+                moves: 2,
+                ..PartCounts::default()
+            })
+        };
+
+        assert_eq!(stats.ticks_until_move(), Some(2));
+        assert_eq!(
+            CreepStats {
+                fatigue: 0,
+                ..stats
+            }
+            .ticks_until_move(),
+            Some(0)
+        );
+    }
+}
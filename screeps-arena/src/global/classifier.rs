@@ -0,0 +1,184 @@
+use screeps_arena::{Creep, Part};
+
+use super::body::CreepStats;
+
+/// Damage of a single attack part against an adjacent target.
+pub const ATTACK_POWER: u32 = 30;
+/// Damage of a single ranged attack part against one target up to 3 tiles away.
+pub const RANGED_ATTACK_POWER: u32 = 10;
+/// Damage of a single ranged attack part doing a mass attack, indexed by `range - 1`.
+pub const RANGED_MASS_ATTACK_POWER: [u32; 3] = [10, 4, 1];
+/// Healing of a single heal part on an adjacent target.
+pub const HEAL_POWER: u32 = 12;
+/// Healing of a single heal part on a target up to 3 tiles away.
+pub const RANGED_HEAL_POWER: u32 = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CombatRole {
+    Melee,
+    Ranger,
+    Healer,
+    Collector,
+    Worker,
+    Unknown,
+}
+
+/// How many of each part still work. Parts that were destroyed (0 hits) do nothing in the game so
+/// they aren't counted.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct PartCounts {
+    pub moves: u32,
+    pub works: u32,
+    pub carries: u32,
+    pub attacks: u32,
+    pub ranged_attacks: u32,
+    pub heals: u32,
+    pub toughs: u32,
+}
+
+impl PartCounts {
+    pub fn from_creep(creep: &Creep) -> Self {
+        Self::count(creep, true)
+    }
+
+    /// Every part the creep was spawned with, destroyed ones included.
+    pub fn spawned_with(creep: &Creep) -> Self {
+        Self::count(creep, false)
+    }
+
+    /// Attack, ranged attack and heal parts.
+    pub fn combat(&self) -> u32 {
+        self.attacks + self.ranged_attacks + self.heals
+    }
+
+    /// Parts that make the creep tired when it moves, leaving out carry parts that only weigh
+    /// something once they hold energy.
+    pub fn weight(&self) -> u32 {
+        self.works + self.attacks + self.ranged_attacks + self.heals + self.toughs
+    }
+
+    fn count(creep: &Creep, working_only: bool) -> Self {
+        let mut counts = Self::default();
+
+        for body_part in creep.body() {
+            if working_only && body_part.hits() == 0 {
+                continue;
+            }
+
+            match body_part.part() {
+                Part::Move => counts.moves += 1,
+                Part::Work => counts.works += 1,
+                Part::Carry => counts.carries += 1,
+                Part::Attack => counts.attacks += 1,
+                Part::RangedAttack => counts.ranged_attacks += 1,
+                Part::Heal => counts.heals += 1,
+                Part::Tough => counts.toughs += 1,
+                _ => (),
+            }
+        }
+
+        counts
+    }
+}
+
+/// What a creep can do to us in a single tick.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ThreatScore {
+    pub melee_dps: u32,
+    /// Single target ranged damage at range 1, 2 and 3.
+    pub ranged_dps: [u32; 3],
+    /// Ranged mass attack damage to each creep at range 1, 2 and 3.
+    pub mass_attack_dps: [u32; 3],
+    pub heal_per_tick: u32,
+    /// Damage needed to kill the creep. Tough parts soak damage like any other part, so this is
+    /// the creep's hits.
+    pub effective_hits: u32,
+}
+
+impl ThreatScore {
+    fn new(stats: &CreepStats, hits: u32) -> Self {
+        Self {
+            melee_dps: stats.melee_dps,
+            ranged_dps: stats.ranged_dps,
+            mass_attack_dps: stats.mass_attack_damage,
+            heal_per_tick: stats.heal_per_tick,
+            effective_hits: hits,
+        }
+    }
+
+    /// The most damage the creep can do to a single target at the range.
+    pub fn damage_at_range(&self, range: u8) -> u32 {
+        match range {
+            0 | 1 => self.melee_dps + self.ranged_dps[0],
+            2 | 3 => self.ranged_dps[range as usize - 1],
+            _ => 0,
+        }
+    }
+
+    /// Damage plus healing the creep puts out per tick when everything is in range.
+    pub fn score(&self) -> u32 {
+        self.melee_dps + self.ranged_dps[0] + self.heal_per_tick
+    }
+
+    /// How much threat is removed per point of damage we deal, so cheap dangerous creeps are
+    /// killed first.
+    pub fn kill_priority(&self) -> f64 {
+        self.score() as f64 / self.effective_hits.max(1) as f64
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Classification {
+    pub parts: PartCounts,
+    pub primary: CombatRole,
+    pub secondary: Option<CombatRole>,
+    pub threat: ThreatScore,
+}
+
+/// Classifies a creep by what its working parts add up to instead of the first part in its body,
+/// so a creep with one carry and ten attack parts is melee with a collector secondary.
+pub fn classify(creep: &Creep) -> Classification {
+    let parts = PartCounts::from_creep(creep);
+    let threat = ThreatScore::new(&CreepStats::from_parts(parts), creep.hits());
+
+    let mut combat_roles = vec![
+        (CombatRole::Melee, threat.melee_dps),
+        (CombatRole::Ranger, threat.ranged_dps[0]),
+        (CombatRole::Healer, threat.heal_per_tick),
+    ]
+    .into_iter()
+    .filter(|(_, weight)| *weight > 0)
+    .collect::<Vec<(CombatRole, u32)>>();
+    // stable sort, so ties keep the melee, ranger, healer order
+    combat_roles.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    let mut roles = combat_roles
+        .into_iter()
+        .map(|(role, _)| role)
+        .collect::<Vec<CombatRole>>();
+    if parts.carries > 0 {
+        roles.push(CombatRole::Collector);
+    }
+    if parts.works > 0 {
+        roles.push(CombatRole::Worker);
+    }
+
+    Classification {
+        parts,
+        primary: roles.first().copied().unwrap_or(CombatRole::Unknown),
+        secondary: roles.get(1).copied(),
+        threat,
+    }
+}
+
+/// The enemy that is best to shoot at, the highest kill priority with the closest one winning
+/// ties.
+pub fn pick_target<'a>(creep: &Creep, enemies: &'a [Creep]) -> Option<&'a Creep> {
+    enemies.iter().max_by(|a, b| {
+        let a_priority = classify(a).threat.kill_priority();
+        let b_priority = classify(b).threat.kill_priority();
+        a_priority
+            .total_cmp(&b_priority)
+            .then_with(|| creep.get_range_to(b).cmp(&creep.get_range_to(a)))
+    })
+}
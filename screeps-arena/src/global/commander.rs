@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+
+use eyre::Result;
+use screeps_arena::Creep;
+
+use super::{
+    classifier::{classify, pick_target, CombatRole},
+    formation::{get_heading, Formation, FormationShape},
+    phase::Phase,
+    ranged::fire_ranged,
+    terrain::get_range,
+    tracker::EnemyTracker,
+    traffic::{TrafficManager, COMBAT_PRIORITY},
+    utilities::create_position_object,
+};
+
+/// Creeps per squad, four make a box formation a 2x2 quad.
+const SQUAD_SIZE: usize = 4;
+/// Enemies this close to home make a squad go back to defend it.
+const DEFEND_RADIUS: u8 = 10;
+/// A squad with an enemy this close to any member is fighting.
+const ENGAGE_RANGE: u8 = 3;
+
+/// What a squad is trying to achieve. Home is whatever the arena has us protect, our spawn or our
+/// flag.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Objective {
+    DefendHome,
+    RaidCollectors,
+    SiegeEnemyHome,
+    HoldFlag,
+}
+
+/// What a single creep has been told to do this tick.
+#[derive(Clone, Debug)]
+pub enum Order {
+    Attack { target: Creep },
+    MoveTo { position: (u8, u8) },
+    Hold,
+}
+
+/// The world as the commander sees it, built by each arena from its own objects.
+pub struct World {
+    pub military: Vec<Creep>,
+    pub enemies: Vec<Creep>,
+    pub home: (u8, u8),
+    pub enemy_home: Option<(u8, u8)>,
+    pub flag: Option<(u8, u8)>,
+    pub phase: Phase,
+}
+
+/// What a squad tells the commander about itself.
+#[derive(Clone, Copy, Debug)]
+pub struct SquadReport {
+    pub size: usize,
+    /// Damage plus healing the squad puts out per tick.
+    pub strength: u32,
+    /// Where the leader is.
+    pub position: (u8, u8),
+    pub engaged: bool,
+}
+
+pub struct Squad {
+    pub objective: Objective,
+    formation: Formation,
+    members: Vec<Creep>,
+}
+
+impl Squad {
+    fn new(objective: Objective) -> Self {
+        Self {
+            objective,
+            formation: Formation::new(FormationShape::Box),
+            members: vec![],
+        }
+    }
+
+    pub fn report(&self, enemies: &[Creep]) -> SquadReport {
+        let position = self
+            .formation
+            .leader(&self.members)
+            .map_or((0, 0), |leader| (leader.x(), leader.y()));
+
+        SquadReport {
+            size: self.members.len(),
+            strength: self
+                .members
+                .iter()
+                .map(|member| classify(member).threat.score())
+                .sum(),
+            position,
+            engaged: self.members.iter().any(|member| {
+                enemies
+                    .iter()
+                    .any(|enemy| member.get_range_to(enemy) <= ENGAGE_RANGE)
+            }),
+        }
+    }
+
+    /// Fights when engaged, otherwise moves toward the objective in formation with the leader
+    /// waiting for the slowest member.
+    fn give_orders(&mut self, world: &World, orders: &mut HashMap<String, Order>) {
+        let leader = match self.formation.leader(&self.members) {
+            Some(leader) => leader,
+            None => return,
+        };
+
+        if self.report(&world.enemies).engaged {
+            for member in &self.members {
+                let nearby_enemies = world
+                    .enemies
+                    .iter()
+                    .filter(|enemy| member.get_range_to(*enemy) <= ENGAGE_RANGE * 2)
+                    .cloned()
+                    .collect::<Vec<Creep>>();
+                let order = match pick_target(member, &nearby_enemies) {
+                    Some(target) => Order::Attack {
+                        target: target.clone(),
+                    },
+                    None => Order::Hold,
+                };
+                insert_order(orders, member, order);
+            }
+            return;
+        }
+
+        let destination = match self.destination(world) {
+            Some(destination) => destination,
+            None => {
+                for member in &self.members {
+                    insert_order(orders, member, Order::Hold);
+                }
+                return;
+            }
+        };
+        let leader_position = (leader.x(), leader.y());
+        let heading = get_heading(leader_position, destination);
+
+        let leader_order = if self.formation.should_regroup(&self.members, heading) {
+            Order::Hold
+        } else {
+            Order::MoveTo {
+                position: destination,
+            }
+        };
+        insert_order(orders, leader, leader_order);
+
+        for (slot, member) in self.formation.followers(&self.members) {
+            let position = self.formation.slot_position(slot, leader_position, heading);
+            insert_order(orders, member, Order::MoveTo { position });
+        }
+    }
+
+    fn destination(&self, world: &World) -> Option<(u8, u8)> {
+        match self.objective {
+            Objective::DefendHome => Some(world.home),
+            Objective::RaidCollectors => {
+                let leader = self.formation.leader(&self.members)?;
+                get_enemy_collectors(&world.enemies)
+                    .into_iter()
+                    .min_by_key(|collector| leader.get_range_to(collector))
+                    .map(|collector| (collector.x(), collector.y()))
+            }
+            Objective::SiegeEnemyHome => world.enemy_home,
+            Objective::HoldFlag => world.flag,
+        }
+    }
+}
+
+/// Splits our military into squads, picks an objective for every squad from what the squads
+/// report and turns those into orders for every creep.
+#[derive(Default)]
+pub struct Commander {
+    squads: Vec<Squad>,
+}
+
+impl Commander {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn squads(&self) -> &[Squad] {
+        &self.squads
+    }
+
+    /// Call once per tick. Returns the order for every military creep by creep id.
+    pub fn tick(&mut self, world: &World) -> HashMap<String, Order> {
+        self.assign_squads(&world.military);
+        self.assign_objectives(world);
+
+        let mut orders = HashMap::new();
+        for squad in self.squads.iter_mut() {
+            squad.give_orders(world, &mut orders);
+        }
+
+        orders
+    }
+
+    /// Drops the dead, puts new creeps in the first squad with room and removes empty squads.
+    fn assign_squads(&mut self, military: &[Creep]) {
+        for squad in self.squads.iter_mut() {
+            squad
+                .members
+                .retain(|member| military.iter().any(|creep| creep.id() == member.id()));
+        }
+        self.squads.retain(|squad| !squad.members.is_empty());
+
+        for creep in military {
+            let is_assigned = self
+                .squads
+                .iter()
+                .any(|squad| squad.members.iter().any(|member| member.id() == creep.id()));
+            if is_assigned {
+                continue;
+            }
+
+            match self
+                .squads
+                .iter_mut()
+                .find(|squad| squad.members.len() < SQUAD_SIZE)
+            {
+                Some(squad) => squad.members.push(creep.clone()),
+                None => {
+                    let mut squad = Squad::new(Objective::SiegeEnemyHome);
+                    squad.members.push(creep.clone());
+                    self.squads.push(squad);
+                }
+            }
+        }
+
+        for squad in self.squads.iter_mut() {
+            // refresh the creeps so positions and hits are from this tick
+            squad.members = squad
+                .members
+                .iter()
+                .filter_map(|member| military.iter().find(|creep| creep.id() == member.id()))
+                .cloned()
+                .collect();
+            squad.formation.update_members(&squad.members);
+        }
+    }
+
+    /// The squad closest to home defends it when enemies come near, all squads do when the
+    /// enemies there are stronger than it. One squad holds the flag if there is one, one raids
+    /// enemy collectors when there are spare squads. The rest siege the enemy's home while we
+    /// push or in the endgame and wait at home otherwise.
+    fn assign_objectives(&mut self, world: &World) {
+        let reports = self
+            .squads
+            .iter()
+            .map(|squad| squad.report(&world.enemies))
+            .collect::<Vec<SquadReport>>();
+        let mut unassigned = (0..self.squads.len()).collect::<Vec<usize>>();
+        // closest to home first
+        unassigned.sort_by_key(|index| get_range(reports[*index].position, world.home));
+
+        let threats = world
+            .enemies
+            .iter()
+            .filter(|enemy| get_range((enemy.x(), enemy.y()), world.home) <= DEFEND_RADIUS)
+            .collect::<Vec<&Creep>>();
+        if !threats.is_empty() {
+            let threat_strength = threats
+                .iter()
+                .map(|enemy| classify(enemy).threat.score())
+                .sum::<u32>();
+            let defender = unassigned.remove(0);
+            self.squads[defender].objective = Objective::DefendHome;
+
+            if threat_strength > reports[defender].strength {
+                for index in unassigned.drain(..) {
+                    self.squads[index].objective = Objective::DefendHome;
+                }
+            }
+        }
+
+        if world.flag.is_some() && !unassigned.is_empty() {
+            let holder = unassigned.remove(0);
+            self.squads[holder].objective = Objective::HoldFlag;
+        }
+
+        if unassigned.len() > 1 && !get_enemy_collectors(&world.enemies).is_empty() {
+            // the squad furthest from home is closest to the enemy collectors
+            if let Some(raider) = unassigned.pop() {
+                self.squads[raider].objective = Objective::RaidCollectors;
+            }
+        }
+
+        let is_attacking = matches!(world.phase, Phase::Push | Phase::Endgame);
+        for index in unassigned {
+            self.squads[index].objective = if is_attacking && world.enemy_home.is_some() {
+                Objective::SiegeEnemyHome
+            } else {
+                Objective::DefendHome
+            };
+        }
+    }
+}
+
+/// Carries out an order, shooting anything in range while moving. Attackers head for where the
+/// tracker expects the target to be by the time they get there.
+pub fn execute_order(
+    creep: &Creep,
+    order: &Order,
+    enemies: &[Creep],
+    tracker: &EnemyTracker,
+    traffic: &mut TrafficManager,
+) -> Result<()> {
+    match order {
+        Order::Attack { target } => {
+            fire_ranged(creep, Some(target), enemies);
+            creep.attack(target);
+            let (x, y) = tracker.intercept_point(target, (creep.x(), creep.y()));
+            traffic.request_move_toward(creep, &create_position_object(x, y), None, COMBAT_PRIORITY)
+        }
+        Order::MoveTo { position } => {
+            attack_in_range(creep, enemies);
+            traffic.request_move_toward(
+                creep,
+                &create_position_object(position.0, position.1),
+                None,
+                COMBAT_PRIORITY,
+            )
+        }
+        Order::Hold => {
+            attack_in_range(creep, enemies);
+            traffic.hold(creep);
+            Ok(())
+        }
+    }
+}
+
+fn attack_in_range(creep: &Creep, enemies: &[Creep]) {
+    let in_range = enemies
+        .iter()
+        .filter(|enemy| creep.get_range_to(*enemy) <= 3)
+        .cloned()
+        .collect::<Vec<Creep>>();
+    let target = pick_target(creep, &in_range);
+    fire_ranged(creep, target, &in_range);
+    if let Some(target) = target {
+        creep.attack(target);
+    }
+}
+
+fn insert_order(orders: &mut HashMap<String, Order>, creep: &Creep, order: Order) {
+    if let Some(id) = creep.id().as_string() {
+        orders.insert(id, order);
+    }
+}
+
+fn get_enemy_collectors(enemies: &[Creep]) -> Vec<&Creep> {
+    enemies
+        .iter()
+        .filter(|enemy| classify(enemy).primary == CombatRole::Collector)
+        .collect()
+}
@@ -0,0 +1,162 @@
+/// Two values around one boundary. A value has to go past `enter` to switch on and come back
+/// past `exit` to switch off again, anything in between keeps the last answer so a value sitting
+/// on the boundary doesn't flip the answer every tick.
+#[derive(Clone, Copy, Debug)]
+pub struct Threshold<T> {
+    pub enter: T,
+    pub exit: T,
+}
+
+impl<T: PartialOrd + Copy> Threshold<T> {
+    pub fn new(enter: T, exit: T) -> Self {
+        Self { enter, exit }
+    }
+
+    /// For values that switch on by rising, `exit` should be below `enter`.
+    pub fn is_above(&self, value: T, is_on: bool) -> bool {
+        if is_on {
+            value >= self.exit
+        } else {
+            value >= self.enter
+        }
+    }
+
+    /// For values that switch on by falling, `exit` should be above `enter`.
+    pub fn is_below(&self, value: T, is_on: bool) -> bool {
+        if is_on {
+            value < self.exit
+        } else {
+            value < self.enter
+        }
+    }
+}
+
+/// A state that only changes once the new state has been wanted for `confirm_ticks` updates in a
+/// row and the current one has lasted at least `min_dwell_ticks`.
+#[derive(Clone, Debug)]
+pub struct Debounced<S> {
+    state: S,
+    ticks_in_state: u32,
+    /// The state that is wanted instead and for how many updates in a row it has been.
+    candidate: Option<(S, u32)>,
+    confirm_ticks: u32,
+    min_dwell_ticks: u32,
+}
+
+impl<S: Copy + PartialEq> Debounced<S> {
+    pub fn new(initial: S, confirm_ticks: u32, min_dwell_ticks: u32) -> Self {
+        Self {
+            state: initial,
+            ticks_in_state: 0,
+            candidate: None,
+            confirm_ticks,
+            min_dwell_ticks,
+        }
+    }
+
+    pub fn state(&self) -> S {
+        self.state
+    }
+
+    /// How many updates the current state has lasted.
+    pub fn ticks_in_state(&self) -> u32 {
+        self.ticks_in_state
+    }
+
+    /// Call once per tick with the state the signals point at. Returns the state to act on.
+    pub fn update(&mut self, wanted: S) -> S {
+        self.ticks_in_state = self.ticks_in_state.saturating_add(1);
+
+        if wanted == self.state {
+            self.candidate = None;
+            return self.state;
+        }
+
+        let ticks_wanted = match self.candidate {
+            Some((candidate, ticks)) if candidate == wanted => ticks + 1,
+            _ => 1,
+        };
+        self.candidate = Some((wanted, ticks_wanted));
+
+        if ticks_wanted >= self.confirm_ticks && self.ticks_in_state >= self.min_dwell_ticks {
+            self.force(wanted);
+        }
+
+        self.state
+    }
+
+    /// Switches right away, for states that can't wait.
+    pub fn force(&mut self, state: S) {
+        if state != self.state {
+            self.ticks_in_state = 0;
+        }
+        self.state = state;
+        self.candidate = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rising_values_switch_on_at_enter_and_off_below_exit() {
+        let threshold = Threshold::new(10, 5);
+
+        assert!(!threshold.is_above(9, false));
+        assert!(threshold.is_above(10, false));
+        assert!(threshold.is_above(5, true));
+        assert!(!threshold.is_above(4, true));
+    }
+
+    #[test]
+    fn falling_values_switch_on_below_enter_and_off_at_exit() {
+        let threshold = Threshold::new(0.4, 0.9);
+
+        assert!(!threshold.is_below(0.5, false));
+        assert!(threshold.is_below(0.3, false));
+        assert!(threshold.is_below(0.8, true));
+        assert!(!threshold.is_below(0.9, true));
+    }
+
+    #[test]
+    fn a_new_state_has_to_be_confirmed() {
+        let mut debounced = Debounced::new('a', 3, 0);
+
+        assert_eq!(debounced.update('b'), 'a');
+        assert_eq!(debounced.update('b'), 'a');
+        assert_eq!(debounced.update('b'), 'b');
+        assert_eq!(debounced.ticks_in_state(), 0);
+    }
+
+    #[test]
+    fn wanting_the_current_state_resets_the_confirmation() {
+        let mut debounced = Debounced::new('a', 2, 0);
+
+        debounced.update('b');
+        debounced.update('a');
+
+        assert_eq!(debounced.update('b'), 'a');
+        assert_eq!(debounced.update('b'), 'b');
+    }
+
+    #[test]
+    fn a_state_lasts_at_least_the_min_dwell_ticks() {
+        let mut debounced = Debounced::new('a', 1, 3);
+
+        assert_eq!(debounced.update('b'), 'a');
+        assert_eq!(debounced.update('b'), 'a');
+        assert_eq!(debounced.update('b'), 'b');
+    }
+
+    #[test]
+    fn force_switches_right_away() {
+        let mut debounced = Debounced::new('a', 5, 5);
+        debounced.update('a');
+
+        debounced.force('b');
+
+        assert_eq!(debounced.state(), 'b');
+        assert_eq!(debounced.ticks_in_state(), 0);
+    }
+}
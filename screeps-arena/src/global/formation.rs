@@ -0,0 +1,247 @@
+use eyre::Result;
+use js_sys::Object;
+use screeps_arena::Creep;
+
+use super::{
+    debounce::{Debounced, Threshold},
+    terrain::{get_range, get_terrain, Terrain, MAP_SIZE},
+    traffic::{TrafficManager, COMBAT_PRIORITY},
+    utilities::{create_position_object, get_position},
+};
+
+/// Members this far from their slot or closer count as in formation.
+const SLOT_TOLERANCE: u8 = 1;
+/// A member this far from its slot makes the leader stop and wait.
+const REGROUP_DISTANCE: u8 = 3;
+/// The leader waits or moves for at least this many ticks before changing its mind.
+const REGROUP_MIN_TICKS: u32 = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FormationShape {
+    /// Side by side with the leader in the middle.
+    Line,
+    /// Rows of two behind the leader, four creeps make a 2x2 quad.
+    Box,
+    /// A V opening up behind the leader.
+    Wedge,
+}
+
+impl FormationShape {
+    /// Where the member in the slot stands relative to the leader, as `(back, side)`. Back is
+    /// measured against the heading and side across it.
+    fn slot_offset(self, slot: usize) -> (i16, i16) {
+        let slot = slot as i16;
+        match self {
+            FormationShape::Line => {
+                let distance = slot / 2 + 1;
+                let side = if slot % 2 == 0 { distance } else { -distance };
+                (0, side)
+            }
+            FormationShape::Box => {
+                // the leader takes the first spot of the first row
+                let spot = slot + 1;
+                (spot / 2, spot % 2)
+            }
+            FormationShape::Wedge => {
+                let distance = slot / 2 + 1;
+                let side = if slot % 2 == 0 { distance } else { -distance };
+                (distance, side)
+            }
+        }
+    }
+}
+
+/// Moves a group of creeps together. The first member is the leader, the rest keep their slots
+/// around it, and the leader only moves on once everyone has caught up so the group stays
+/// together over swamps.
+pub struct Formation {
+    shape: FormationShape,
+    /// Leader first, the slot of every other member is its index minus one.
+    member_ids: Vec<String>,
+    regrouping: Debounced<bool>,
+}
+
+impl Formation {
+    pub fn new(shape: FormationShape) -> Self {
+        Self {
+            shape,
+            member_ids: vec![],
+            regrouping: Debounced::new(false, 1, REGROUP_MIN_TICKS),
+        }
+    }
+
+    pub fn shape(&self) -> FormationShape {
+        self.shape
+    }
+
+    /// Drops the members that died and gives new members the free slots at the back. When the
+    /// leader died the next member takes over and everyone moves up a slot.
+    pub fn update_members(&mut self, members: &[Creep]) {
+        let alive = members
+            .iter()
+            .filter_map(|member| member.id().as_string())
+            .collect::<Vec<String>>();
+
+        self.member_ids.retain(|id| alive.contains(id));
+        for id in alive {
+            if !self.member_ids.contains(&id) {
+                self.member_ids.push(id);
+            }
+        }
+    }
+
+    pub fn leader<'a>(&self, members: &'a [Creep]) -> Option<&'a Creep> {
+        let leader_id = self.member_ids.first()?;
+        members
+            .iter()
+            .find(|member| member.id().as_string().as_ref() == Some(leader_id))
+    }
+
+    /// The tile the member in the slot should stand on when the leader is at `leader` facing
+    /// `heading`.
+    pub fn slot_position(&self, slot: usize, leader: (u8, u8), heading: (i8, i8)) -> (u8, u8) {
+        let (back, side) = self.shape.slot_offset(slot);
+        let (heading_x, heading_y) = (heading.0 as i16, heading.1 as i16);
+        // perpendicular to the heading
+        let (side_x, side_y) = (-heading_y, heading_x);
+        let x = leader.0 as i16 - back * heading_x + side * side_x;
+        let y = leader.1 as i16 - back * heading_y + side * side_y;
+
+        let max = MAP_SIZE as i16 - 1;
+        find_standable_tile((x.clamp(0, max) as u8, y.clamp(0, max) as u8))
+    }
+
+    /// True while the leader should wait for the others. The group starts regrouping once a
+    /// member falls `REGROUP_DISTANCE` behind its slot and only moves on when everyone is back
+    /// next to their slot, so a member hovering at the edge doesn't stop the group every other
+    /// tick. Fatigued members are always waited for.
+    pub fn should_regroup(&mut self, members: &[Creep], heading: (i8, i8)) -> bool {
+        let leader = match self.leader(members) {
+            Some(leader) => leader,
+            None => return false,
+        };
+        let leader_position = (leader.x(), leader.y());
+
+        let mut furthest = 0;
+        let mut is_fatigued = false;
+        for (slot, member) in self.followers(members) {
+            let slot_position = self.slot_position(slot, leader_position, heading);
+            furthest = furthest.max(get_range((member.x(), member.y()), slot_position));
+            is_fatigued |= member.fatigue() > 0;
+        }
+
+        let threshold = Threshold::new(REGROUP_DISTANCE, SLOT_TOLERANCE + 1);
+        let wanted = threshold.is_above(furthest, self.regrouping.state());
+        self.regrouping.update(wanted) || is_fatigued
+    }
+
+    /// Moves the whole formation one step toward the target. Members head for their slots
+    /// while the leader waits for the slowest of them.
+    pub fn move_toward(
+        &mut self,
+        members: &[Creep],
+        target: &Object,
+        traffic: &mut TrafficManager,
+    ) -> Result<()> {
+        self.update_members(members);
+        let leader = match self.leader(members) {
+            Some(leader) => leader,
+            None => return Ok(()),
+        };
+
+        let leader_position = (leader.x(), leader.y());
+        let heading = get_heading(leader_position, get_position(target)?);
+
+        if self.should_regroup(members, heading) {
+            traffic.hold(leader);
+        } else {
+            traffic.request_move_toward(leader, target, None, COMBAT_PRIORITY)?;
+        }
+
+        for (slot, member) in self.followers(members) {
+            let (x, y) = self.slot_position(slot, leader_position, heading);
+            if (member.x(), member.y()) == (x, y) {
+                traffic.idle(member);
+            } else {
+                traffic.request_move_toward(
+                    member,
+                    &create_position_object(x, y),
+                    None,
+                    COMBAT_PRIORITY,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Every member except the leader together with its slot.
+    pub fn followers<'a>(
+        &'a self,
+        members: &'a [Creep],
+    ) -> impl Iterator<Item = (usize, &'a Creep)> {
+        self.member_ids
+            .iter()
+            .skip(1)
+            .enumerate()
+            .filter_map(move |(slot, id)| {
+                members
+                    .iter()
+                    .find(|member| member.id().as_string().as_ref() == Some(id))
+                    .map(|member| (slot, member))
+            })
+    }
+}
+
+/// The direction from one tile to another as a step of -1, 0 or 1 on each axis. Standing on the
+/// target faces up.
+pub fn get_heading(from: (u8, u8), to: (u8, u8)) -> (i8, i8) {
+    let heading = (
+        (to.0 as i16 - from.0 as i16).signum() as i8,
+        (to.1 as i16 - from.1 as i16).signum() as i8,
+    );
+
+    if heading == (0, 0) {
+        (0, -1)
+    } else {
+        heading
+    }
+}
+
+/// Slots on a wall move to the closest walkable tile, slots in a swamp move to a neighboring
+/// plain if there is one so the member isn't slowed down.
+pub fn find_standable_tile(tile: (u8, u8)) -> (u8, u8) {
+    let terrain = get_terrain(tile.0, tile.1);
+    if terrain == Terrain::Plain {
+        return tile;
+    }
+
+    let neighbors = (-1..=1)
+        .flat_map(|dx| (-1..=1).map(move |dy| (dx, dy)))
+        .filter_map(|(dx, dy)| {
+            let x = tile.0 as i16 + dx;
+            let y = tile.1 as i16 + dy;
+            if x < 0 || y < 0 || x >= MAP_SIZE as i16 || y >= MAP_SIZE as i16 {
+                None
+            } else {
+                Some((x as u8, y as u8))
+            }
+        })
+        .map(|neighbor| (neighbor, get_terrain(neighbor.0, neighbor.1)))
+        .collect::<Vec<((u8, u8), Terrain)>>();
+
+    if let Some((plain, _)) = neighbors
+        .iter()
+        .find(|(_, terrain)| *terrain == Terrain::Plain)
+    {
+        return *plain;
+    }
+
+    match terrain {
+        Terrain::Wall => neighbors
+            .iter()
+            .find(|(_, terrain)| *terrain != Terrain::Wall)
+            .map_or(tile, |(neighbor, _)| *neighbor),
+        _ => tile,
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use eyre::Result;
+use screeps_arena::Creep;
+
+use super::{
+    classifier::{classify, HEAL_POWER, RANGED_HEAL_POWER},
+    traffic::{TrafficManager, HEALER_PRIORITY},
+};
+
+/// Healers don't walk further than this to reach a patient, anyone further away is left to the
+/// healers closer to them.
+const MAX_APPROACH_RANGE: u8 = 10;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HealKind {
+    /// The patient is next to the healer, or is the healer.
+    Heal,
+    /// The patient is 2 or 3 tiles away, ranged heals are a third as strong.
+    RangedHeal,
+    /// The patient is out of reach, the healer walks toward it first.
+    Approach,
+}
+
+#[derive(Clone, Debug)]
+pub struct HealOrder {
+    pub target: Creep,
+    pub kind: HealKind,
+}
+
+struct Patient {
+    creep: Creep,
+    /// The hits the creep is expected to have at the end of next tick without any healing.
+    expected_hits: i64,
+    predicted_damage: u32,
+    /// Healing already planned for the creep.
+    planned: u32,
+}
+
+impl Patient {
+    /// Healing the creep needs to survive next tick.
+    fn needed_to_survive(&self) -> u32 {
+        if self.expected_hits > 0 {
+            0
+        } else {
+            (1 - self.expected_hits) as u32
+        }
+    }
+
+    /// Healing the creep needs to end next tick at full hits.
+    fn needed_to_top_up(&self) -> u32 {
+        self.creep.hits_max() - self.creep.hits() + self.predicted_damage
+    }
+}
+
+/// Decides who every healer heals this tick. Creeps that would die from the damage predicted for
+/// next tick are healed first with as many healers as it takes to keep them alive, then healers
+/// that are left over top up whoever is missing the most hits. Creeps about to be hit are healed
+/// before they are damaged since heals and damage land in the same tick. Creeps that would die
+/// even with every healer in reach on them are given up so the healers go where they help.
+pub struct HealPlan {
+    orders: HashMap<String, HealOrder>,
+}
+
+impl HealPlan {
+    pub fn new(healers: &[Creep], patients: &[Creep], enemies: &[Creep]) -> Self {
+        let mut patients = patients
+            .iter()
+            .filter_map(|creep| {
+                let predicted_damage = predict_incoming_damage(creep, enemies);
+                let is_hurt = creep.hits() < creep.hits_max();
+                if !is_hurt && predicted_damage == 0 {
+                    return None;
+                }
+
+                Some(Patient {
+                    creep: creep.clone(),
+                    expected_hits: creep.hits() as i64 - predicted_damage as i64,
+                    predicted_damage,
+                    planned: 0,
+                })
+            })
+            .collect::<Vec<Patient>>();
+        let mut free_healers = healers
+            .iter()
+            .filter(|healer| classify(healer).parts.heals > 0)
+            .collect::<Vec<&Creep>>();
+        let mut orders = HashMap::new();
+
+        patients.retain(|patient| {
+            patient.needed_to_survive() <= available_healing(&patient.creep, &free_healers)
+        });
+
+        // the creeps closest to dying first
+        patients.sort_by_key(|patient| patient.expected_hits);
+        for patient in patients.iter_mut() {
+            while patient.planned < patient.needed_to_survive() {
+                if !assign_closest_healer(patient, &mut free_healers, &mut orders) {
+                    break;
+                }
+            }
+        }
+
+        // then whoever is missing the most hits
+        patients.sort_by_key(|patient| patient.planned as i64 - patient.needed_to_top_up() as i64);
+        for patient in patients.iter_mut() {
+            while patient.planned < patient.needed_to_top_up() {
+                if !assign_closest_healer(patient, &mut free_healers, &mut orders) {
+                    break;
+                }
+            }
+        }
+
+        Self { orders }
+    }
+
+    pub fn order_for(&self, healer: &Creep) -> Option<&HealOrder> {
+        healer.id().as_string().and_then(|id| self.orders.get(&id))
+    }
+}
+
+/// Heals the target of the order, moving next to it unless it already is.
+pub fn execute_heal(creep: &Creep, order: &HealOrder, traffic: &mut TrafficManager) -> Result<()> {
+    match order.kind {
+        HealKind::Heal => {
+            creep.heal(&order.target);
+        }
+        HealKind::RangedHeal => {
+            creep.ranged_heal(&order.target);
+        }
+        HealKind::Approach => (),
+    }
+
+    if creep.get_range_to(&order.target) > 1 {
+        traffic.request_move_toward(creep, &order.target, None, HEALER_PRIORITY)
+    } else {
+        traffic.idle(creep);
+        Ok(())
+    }
+}
+
+/// The damage the creep can expect next tick from the enemies around it, assuming every enemy
+/// that isn't fatigued steps one tile closer first.
+pub fn predict_incoming_damage(creep: &Creep, enemies: &[Creep]) -> u32 {
+    enemies
+        .iter()
+        .map(|enemy| {
+            let range = creep.get_range_to(enemy);
+            let range = if enemy.fatigue() == 0 {
+                range.saturating_sub(1)
+            } else {
+                range
+            };
+            classify(enemy).threat.damage_at_range(range)
+        })
+        .sum()
+}
+
+/// Gives the patient the closest free healer that can reach it. Returns false when there is
+/// none left, or when the closest one still has to walk over, since every healer after it is
+/// even further away.
+fn assign_closest_healer(
+    patient: &mut Patient,
+    free_healers: &mut Vec<&Creep>,
+    orders: &mut HashMap<String, HealOrder>,
+) -> bool {
+    let closest = free_healers
+        .iter()
+        .enumerate()
+        .filter(|(_, healer)| healer.get_range_to(&patient.creep) <= MAX_APPROACH_RANGE)
+        .min_by_key(|(_, healer)| healer.get_range_to(&patient.creep))
+        .map(|(index, _)| index);
+    let healer = match closest {
+        Some(index) => free_healers.remove(index),
+        None => return false,
+    };
+
+    let (kind, healing) = get_healing(healer, &patient.creep);
+    patient.planned += healing;
+
+    if let Some(id) = healer.id().as_string() {
+        orders.insert(
+            id,
+            HealOrder {
+                target: patient.creep.clone(),
+                kind,
+            },
+        );
+    }
+
+    kind != HealKind::Approach
+}
+
+/// Healing every one of the healers could land on the patient next tick.
+fn available_healing(patient: &Creep, healers: &[&Creep]) -> u32 {
+    healers
+        .iter()
+        .map(|healer| get_healing(healer, patient).1)
+        .sum()
+}
+
+/// How the healer would heal the patient from where it stands, and for how much.
+fn get_healing(healer: &Creep, patient: &Creep) -> (HealKind, u32) {
+    let heals = classify(healer).parts.heals;
+    match healer.get_range_to(patient) {
+        0..=1 => (HealKind::Heal, heals * HEAL_POWER),
+        2..=3 => (HealKind::RangedHeal, heals * RANGED_HEAL_POWER),
+        _ => (HealKind::Approach, 0),
+    }
+}
@@ -0,0 +1,257 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use js_sys::{Object, Reflect};
+use screeps_arena::{
+    game::utils::get_objects_by_prototype, prototypes, Creep, OwnedStructureProperties,
+    ResourceType, StructureTower,
+};
+use wasm_bindgen::{prelude::*, JsValue};
+
+use super::{
+    classifier::{classify, HEAL_POWER, RANGED_HEAL_POWER},
+    terrain::{get_range, get_terrain, Terrain, MAP_SIZE},
+    utilities::get_creeps,
+};
+
+pub const TOWER_POWER_ATTACK: u32 = 150;
+pub const TOWER_POWER_HEAL: u32 = 100;
+/// Energy a tower uses per action, towers with less than this can't fire.
+pub const TOWER_ENERGY_COST: u32 = 10;
+const TOWER_OPTIMAL_RANGE: u8 = 5;
+const TOWER_FALLOFF_RANGE: u8 = 20;
+/// Share of the tower's power lost at the falloff range.
+const TOWER_FALLOFF: f64 = 0.75;
+/// Path cost of a tile we would die on, the pathfinder never walks onto it.
+pub const IMPASSABLE_COST: u8 = 255;
+
+#[wasm_bindgen(module = "game/path-finder")]
+extern "C" {
+    pub type CostMatrix;
+
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> CostMatrix;
+
+    #[wasm_bindgen(method)]
+    pub fn set(this: &CostMatrix, x: u8, y: u8, cost: u8);
+
+    /// A new matrix with the same costs, changing it leaves this one alone.
+    #[wasm_bindgen(method, js_name = clone)]
+    pub fn copy(this: &CostMatrix) -> CostMatrix;
+}
+
+/// Damage a tower does, or heals, at the range. Full power up to the optimal range, then falling
+/// off linearly until the falloff range.
+pub fn tower_power_at_range(power: u32, range: u8) -> u32 {
+    let range = range.clamp(TOWER_OPTIMAL_RANGE, TOWER_FALLOFF_RANGE);
+    let falloff = (range - TOWER_OPTIMAL_RANGE) as f64
+        / (TOWER_FALLOFF_RANGE - TOWER_OPTIMAL_RANGE) as f64
+        * TOWER_FALLOFF;
+
+    (power as f64 * (1.0 - falloff)) as u32
+}
+
+/// The damage the enemy can do and the healing we can give on every tile of the map. Rebuild it
+/// every tick, it is only a snapshot of where everything is now.
+pub struct InfluenceMap {
+    damage: Vec<u32>,
+    support: Vec<u32>,
+    /// Building a cost matrix touches every tile, so the move options are kept per hits value.
+    move_options: RefCell<HashMap<u32, Object>>,
+}
+
+impl InfluenceMap {
+    pub fn new(
+        my_creeps: &[Creep],
+        enemies: &[Creep],
+        my_towers: &[StructureTower],
+        enemy_towers: &[StructureTower],
+    ) -> Self {
+        let tile_count = MAP_SIZE as usize * MAP_SIZE as usize;
+        let mut map = Self {
+            damage: vec![0; tile_count],
+            support: vec![0; tile_count],
+            move_options: RefCell::new(HashMap::new()),
+        };
+
+        for enemy in enemies {
+            map.add_creep_damage(enemy);
+        }
+        for creep in my_creeps {
+            map.add_creep_support(creep);
+        }
+        for tower in enemy_towers.iter().filter(|tower| can_fire(tower)) {
+            let position = (tower.x(), tower.y());
+            add_around(&mut map.damage, position, MAP_SIZE, |range| {
+                tower_power_at_range(TOWER_POWER_ATTACK, range)
+            });
+        }
+        for tower in my_towers.iter().filter(|tower| can_fire(tower)) {
+            let position = (tower.x(), tower.y());
+            add_around(&mut map.support, position, MAP_SIZE, |range| {
+                tower_power_at_range(TOWER_POWER_HEAL, range)
+            });
+        }
+
+        map
+    }
+
+    /// Builds the map from every creep and tower in the game.
+    pub fn from_game() -> Self {
+        let (my_towers, enemy_towers): (Vec<StructureTower>, Vec<StructureTower>) =
+            get_objects_by_prototype(prototypes::STRUCTURE_TOWER)
+                .into_iter()
+                .partition(|tower| tower.my().unwrap_or_default());
+
+        Self::new(
+            &get_creeps(true),
+            &get_creeps(false),
+            &my_towers,
+            &enemy_towers,
+        )
+    }
+
+    /// Melee damage reaches the adjacent tiles, ranged damage reaches 3 tiles.
+    fn add_creep_damage(&mut self, enemy: &Creep) {
+        let threat = classify(enemy).threat;
+        add_around(&mut self.damage, (enemy.x(), enemy.y()), 3, |range| {
+            threat.damage_at_range(range)
+        });
+    }
+
+    fn add_creep_support(&mut self, creep: &Creep) {
+        let heals = classify(creep).parts.heals;
+        if heals == 0 {
+            return;
+        }
+
+        add_around(&mut self.support, (creep.x(), creep.y()), 3, |range| {
+            if range <= 1 {
+                heals * HEAL_POWER
+            } else {
+                heals * RANGED_HEAL_POWER
+            }
+        });
+    }
+
+    pub fn damage_at(&self, x: u8, y: u8) -> u32 {
+        index(x, y).map_or(0, |index| self.damage[index])
+    }
+
+    pub fn support_at(&self, x: u8, y: u8) -> u32 {
+        index(x, y).map_or(0, |index| self.support[index])
+    }
+
+    /// Damage left over once our healing on the tile is taken off.
+    pub fn net_damage_at(&self, x: u8, y: u8) -> u32 {
+        self.damage_at(x, y).saturating_sub(self.support_at(x, y))
+    }
+
+    pub fn is_safe(&self, x: u8, y: u8) -> bool {
+        self.damage_at(x, y) == 0
+    }
+
+    /// True when a creep with `hits` standing on the tile would die this tick.
+    pub fn is_lethal(&self, x: u8, y: u8, hits: u32) -> bool {
+        self.net_damage_at(x, y) >= hits
+    }
+
+    /// The walkable tile within `range` of the position taking the least net damage, closest
+    /// tiles winning ties.
+    pub fn safest_position_near(&self, x: u8, y: u8, range: u8) -> (u8, u8) {
+        let mut safest = (x, y);
+        let mut safest_score = (self.net_damage_at(x, y), 0);
+
+        for (tile, tile_range) in tiles_around((x, y), range) {
+            if get_terrain(tile.0, tile.1) == Terrain::Wall {
+                continue;
+            }
+
+            let score = (self.net_damage_at(tile.0, tile.1), tile_range);
+            if score < safest_score {
+                safest = tile;
+                safest_score = score;
+            }
+        }
+
+        safest
+    }
+
+    /// Path costs that make creeps walk around damage in proportion to how much of it there is,
+    /// and never onto a tile that would kill a creep with `hits`.
+    pub fn cost_matrix(&self, hits: u32) -> CostMatrix {
+        let cost_matrix = CostMatrix::new();
+
+        for x in 0..MAP_SIZE {
+            for y in 0..MAP_SIZE {
+                let net_damage = self.net_damage_at(x, y);
+                if net_damage == 0 {
+                    continue;
+                }
+
+                let cost = if net_damage >= hits {
+                    IMPASSABLE_COST
+                } else {
+                    let terrain_cost = match get_terrain(x, y) {
+                        Terrain::Plain => 2,
+                        Terrain::Swamp => 10,
+                        Terrain::Wall => continue,
+                    };
+                    (terrain_cost + net_damage / 10).min(IMPASSABLE_COST as u32 - 1) as u8
+                };
+                cost_matrix.set(x, y, cost);
+            }
+        }
+
+        cost_matrix
+    }
+
+    /// Options for `move_to` and `find_path_to` that path around the damage on the map.
+    pub fn move_options(&self, hits: u32) -> Object {
+        self.move_options
+            .borrow_mut()
+            .entry(hits)
+            .or_insert_with(|| {
+                let options = Object::new();
+                // setting a property on a plain object we just created cannot fail
+                Reflect::set(
+                    &options,
+                    &JsValue::from_str("costMatrix"),
+                    &self.cost_matrix(hits),
+                )
+                .ok();
+                options
+            })
+            .clone()
+    }
+}
+
+fn can_fire(tower: &StructureTower) -> bool {
+    tower.store().get_used_capacity(Some(ResourceType::Energy)) >= TOWER_ENERGY_COST
+}
+
+fn index(x: u8, y: u8) -> Option<usize> {
+    if x >= MAP_SIZE || y >= MAP_SIZE {
+        return None;
+    }
+
+    Some(y as usize * MAP_SIZE as usize + x as usize)
+}
+
+/// Every tile on the map within `range` of the position, together with its range.
+fn tiles_around(position: (u8, u8), range: u8) -> impl Iterator<Item = ((u8, u8), u8)> {
+    let min_x = position.0.saturating_sub(range);
+    let max_x = position.0.saturating_add(range).min(MAP_SIZE - 1);
+    let min_y = position.1.saturating_sub(range);
+    let max_y = position.1.saturating_add(range).min(MAP_SIZE - 1);
+
+    (min_x..=max_x)
+        .flat_map(move |x| (min_y..=max_y).map(move |y| ((x, y), get_range(position, (x, y)))))
+}
+
+fn add_around(layer: &mut [u32], position: (u8, u8), range: u8, value: impl Fn(u8) -> u32) {
+    for ((x, y), tile_range) in tiles_around(position, range) {
+        if let Some(index) = index(x, y) {
+            layer[index] += value(tile_range);
+        }
+    }
+}
@@ -1,2 +1,24 @@
+pub mod body;
+pub mod classifier;
+pub mod commander;
+pub mod debounce;
+pub mod formation;
+pub mod healing;
+pub mod influence;
+pub mod movement;
+pub mod pairing;
+pub mod phase;
+pub mod ranged;
+pub mod retreat;
 pub mod role;
+pub mod routing;
+pub mod stats;
+pub mod strategy;
+pub mod stuck;
+pub mod terrain;
+pub mod tower;
+pub mod tracker;
+pub mod traffic;
 pub mod utilities;
+#[cfg(feature = "debug-visuals")]
+pub mod visuals;
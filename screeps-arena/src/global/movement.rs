@@ -0,0 +1,174 @@
+use super::{
+    body::{CreepStats, MOVE_POWER},
+    classifier::PartCounts,
+    terrain::{get_terrain, Terrain},
+};
+
+/// How many plain and swamp tiles a path crosses.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct TerrainMix {
+    pub plains: u32,
+    pub swamps: u32,
+}
+
+impl TerrainMix {
+    pub fn from_path(path: &[(u8, u8)]) -> Self {
+        let mut mix = Self::default();
+
+        for (x, y) in path {
+            match get_terrain(*x, *y) {
+                Terrain::Plain => mix.plains += 1,
+                Terrain::Swamp => mix.swamps += 1,
+                Terrain::Wall => (),
+            }
+        }
+
+        mix
+    }
+
+    pub fn tiles(&self) -> u32 {
+        self.plains + self.swamps
+    }
+}
+
+/// Ticks until the creep stands on the last tile of the path, starting from the fatigue it has
+/// now. Every step adds the fatigue of the tile stepped onto, the move parts take it off again
+/// each tick, including the tick of the step, and the creep only moves once it is back to 0. None
+/// when the path crosses a wall or the creep has no working move parts.
+pub fn estimate_arrival(stats: &CreepStats, path: &[(u8, u8)]) -> Option<u32> {
+    estimate_arrival_over(stats, path.iter().map(|(x, y)| get_terrain(*x, *y)))
+}
+
+/// Same as `estimate_arrival` for a path that is already known by its terrain.
+pub fn estimate_arrival_over(
+    stats: &CreepStats,
+    terrains: impl IntoIterator<Item = Terrain>,
+) -> Option<u32> {
+    let recovery = stats.parts.moves * MOVE_POWER;
+    let mut fatigue = stats.fatigue;
+    let mut ticks = 0;
+
+    for terrain in terrains {
+        if recovery == 0 {
+            return None;
+        }
+
+        // wait out the fatigue from the last step
+        ticks += (fatigue + recovery - 1) / recovery;
+
+        ticks += 1;
+        fatigue = stats.fatigue_per_step(terrain)?.saturating_sub(recovery);
+    }
+
+    Some(ticks)
+}
+
+/// The fewest move parts a body of the weight needs to cross the terrain mix at no more than
+/// `ticks_per_step` ticks per tile on average. A single tick per tile is as fast as any creep
+/// goes, and a body that weighs nothing needs a single move part to go anywhere.
+pub fn moves_needed(weight: u32, mix: TerrainMix, ticks_per_step: u32) -> u32 {
+    let allowed_ticks = mix.tiles() * ticks_per_step.max(1);
+    // enough to cross a swamp every tick, more moves never help
+    let swamp_fatigue = weight * Terrain::Swamp.fatigue_per_part().unwrap_or_default();
+    let max_moves = ((swamp_fatigue + MOVE_POWER - 1) / MOVE_POWER).max(1);
+
+    (1..=max_moves)
+        .find(|moves| {
+            let stats = CreepStats {
+                parts: PartCounts {
+                    moves: *moves,
+                    ..PartCounts::default()
+                },
+                weight,
+                ..CreepStats::default()
+            };
+            let ticks = mix.plains * stats.ticks_per_step(Terrain::Plain).unwrap_or_default()
+                + mix.swamps * stats.ticks_per_step(Terrain::Swamp).unwrap_or_default();
+
+            ticks <= allowed_ticks
+        })
+        .unwrap_or(max_moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(moves: u32, weight: u32, fatigue: u32) -> CreepStats {
+        CreepStats {
+            parts: PartCounts {
+                moves,
+                ..PartCounts::default()
+            },
+            weight,
+            fatigue,
+            ..CreepStats::default()
+        }
+    }
+
+    #[test]
+    fn balanced_creeps_cross_plains_every_tick() {
+        let terrains = [Terrain::Plain; 3];
+
+        assert_eq!(estimate_arrival_over(&stats(1, 1, 0), terrains), Some(3));
+    }
+
+    #[test]
+    fn swamps_wait_out_the_fatigue_of_the_last_step() {
+        let terrains = [Terrain::Swamp, Terrain::Swamp];
+
+        // the second step waits 4 ticks for the 8 fatigue left from the first
+        assert_eq!(estimate_arrival_over(&stats(1, 1, 0), terrains), Some(6));
+    }
+
+    #[test]
+    fn current_fatigue_delays_the_first_step() {
+        assert_eq!(
+            estimate_arrival_over(&stats(1, 1, 4), [Terrain::Plain]),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn arrival_needs_moves_and_no_walls() {
+        assert_eq!(
+            estimate_arrival_over(&stats(0, 1, 0), [Terrain::Plain]),
+            None
+        );
+        assert_eq!(
+            estimate_arrival_over(&stats(1, 1, 0), [Terrain::Wall]),
+            None
+        );
+        assert_eq!(estimate_arrival_over(&stats(0, 1, 0), []), Some(0));
+    }
+
+    #[test]
+    fn moves_needed_for_full_speed_cover_the_swamps() {
+        let mix = TerrainMix {
+            plains: 2,
+            swamps: 2,
+        };
+
+        assert_eq!(moves_needed(4, mix, 1), 20);
+    }
+
+    #[test]
+    fn slower_paces_need_fewer_moves() {
+        let mix = TerrainMix {
+            plains: 2,
+            swamps: 2,
+        };
+
+        assert_eq!(moves_needed(4, mix, 2), 7);
+    }
+
+    #[test]
+    fn weightless_bodies_need_a_single_move() {
+        let mix = TerrainMix {
+            plains: 5,
+            swamps: 5,
+        };
+
+        assert_eq!(moves_needed(0, mix, 1), 1);
+    }
+}
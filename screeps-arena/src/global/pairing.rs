@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use eyre::Result;
+use screeps_arena::Creep;
+
+use super::{
+    formation::{find_standable_tile, get_heading},
+    healing::predict_incoming_damage,
+    terrain::MAP_SIZE,
+    traffic::{TrafficManager, HEALER_PRIORITY},
+    utilities::create_position_object,
+};
+
+/// Binds every healer to the attackers it looks after. When there are more attackers than
+/// healers a healer looks after several of them, when there are more healers several healers
+/// look after the same attacker. Partners that die are replaced on the next update.
+#[derive(Default)]
+pub struct Pairings {
+    /// Healer id to the ids of its partners, the first partner is the one it follows.
+    partners: HashMap<String, Vec<String>>,
+}
+
+impl Pairings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops the dead and pairs up everyone left without a partner. Call once per tick.
+    pub fn update(&mut self, healers: &[Creep], attackers: &[Creep]) {
+        let healer_ids = get_ids(healers);
+        let attacker_ids = get_ids(attackers);
+
+        self.partners
+            .retain(|healer_id, _| healer_ids.contains(healer_id));
+        for partner_ids in self.partners.values_mut() {
+            partner_ids.retain(|partner_id| attacker_ids.contains(partner_id));
+        }
+        for healer_id in &healer_ids {
+            self.partners.entry(healer_id.clone()).or_default();
+        }
+        if healers.is_empty() || attackers.is_empty() {
+            return;
+        }
+
+        // every attacker gets a healer, the healer with the fewest partners taking it
+        for attacker in attackers {
+            let attacker_id = match attacker.id().as_string() {
+                Some(id) => id,
+                None => continue,
+            };
+            if self.healer_count(&attacker_id) > 0 {
+                continue;
+            }
+
+            let healer = healers
+                .iter()
+                .filter_map(|healer| Some((healer, healer.id().as_string()?)))
+                .min_by_key(|(healer, healer_id)| {
+                    (
+                        self.partners.get(healer_id).map_or(0, Vec::len),
+                        healer.get_range_to(attacker),
+                    )
+                });
+            if let Some((_, healer_id)) = healer {
+                self.partners
+                    .entry(healer_id)
+                    .or_default()
+                    .push(attacker_id);
+            }
+        }
+
+        // healers left over join the attacker with the fewest healers
+        for healer in healers {
+            let healer_id = match healer.id().as_string() {
+                Some(id) => id,
+                None => continue,
+            };
+            if self
+                .partners
+                .get(&healer_id)
+                .map_or(false, |ids| !ids.is_empty())
+            {
+                continue;
+            }
+
+            let attacker = attackers
+                .iter()
+                .filter_map(|attacker| Some((attacker, attacker.id().as_string()?)))
+                .min_by_key(|(attacker, attacker_id)| {
+                    (
+                        self.healer_count(attacker_id),
+                        healer.get_range_to(*attacker),
+                    )
+                });
+            if let Some((_, attacker_id)) = attacker {
+                self.partners
+                    .entry(healer_id)
+                    .or_default()
+                    .push(attacker_id);
+            }
+        }
+    }
+
+    /// The partners of the healer that are in `creeps`, the one it follows first.
+    pub fn partners<'a>(&self, healer: &Creep, creeps: &'a [Creep]) -> Vec<&'a Creep> {
+        let partner_ids = match healer
+            .id()
+            .as_string()
+            .and_then(|id| self.partners.get(&id))
+        {
+            Some(partner_ids) => partner_ids,
+            None => return vec![],
+        };
+
+        partner_ids
+            .iter()
+            .filter_map(|partner_id| {
+                creeps
+                    .iter()
+                    .find(|creep| creep.id().as_string().as_ref() == Some(partner_id))
+            })
+            .collect()
+    }
+
+    fn healer_count(&self, attacker_id: &str) -> usize {
+        self.partners
+            .values()
+            .filter(|partner_ids| partner_ids.iter().any(|id| id == attacker_id))
+            .count()
+    }
+}
+
+/// Keeps the healer one tile behind its first partner, on the side away from the closest enemy,
+/// and heals whichever partner needs it most. Partners about to be hit are healed before they are
+/// damaged, the healer heals itself when none of them need it and it is hurt.
+pub fn run_bodyguard(
+    healer: &Creep,
+    partners: &[&Creep],
+    enemies: &[Creep],
+    traffic: &mut TrafficManager,
+) -> Result<()> {
+    let leader = match partners.first() {
+        Some(leader) => *leader,
+        None => return Ok(()),
+    };
+
+    let patient = partners
+        .iter()
+        .filter(|partner| healer.get_range_to(**partner) <= 3)
+        .map(|partner| {
+            let missing_hits = partner.hits_max() - partner.hits();
+            (
+                *partner,
+                missing_hits + predict_incoming_damage(partner, enemies),
+            )
+        })
+        .filter(|(_, needed)| *needed > 0)
+        .max_by_key(|(_, needed)| *needed)
+        .map(|(partner, _)| partner);
+    match patient {
+        Some(patient) if healer.get_range_to(patient) <= 1 => {
+            healer.heal(patient);
+        }
+        Some(patient) => {
+            healer.ranged_heal(patient);
+        }
+        None if healer.hits() < healer.hits_max() => {
+            healer.heal(healer);
+        }
+        None => (),
+    }
+
+    match get_guard_position(leader, enemies) {
+        Some((x, y)) if (healer.x(), healer.y()) != (x, y) => traffic.request_move_toward(
+            healer,
+            &create_position_object(x, y),
+            None,
+            HEALER_PRIORITY,
+        ),
+        // without enemies anywhere next to the partner is fine
+        None if healer.get_range_to(leader) > 1 => {
+            traffic.request_move_toward(healer, leader, None, HEALER_PRIORITY)
+        }
+        _ => {
+            traffic.idle(healer);
+            Ok(())
+        }
+    }
+}
+
+/// The tile next to the partner facing away from the closest enemy.
+fn get_guard_position(partner: &Creep, enemies: &[Creep]) -> Option<(u8, u8)> {
+    let partner_position = (partner.x(), partner.y());
+    let closest_enemy = enemies
+        .iter()
+        .min_by_key(|enemy| partner.get_range_to(*enemy))?;
+    let enemy_position = (closest_enemy.x(), closest_enemy.y());
+
+    let (heading_x, heading_y) = get_heading(enemy_position, partner_position);
+    let max = MAP_SIZE as i16 - 1;
+    let x = (partner_position.0 as i16 + heading_x as i16).clamp(0, max) as u8;
+    let y = (partner_position.1 as i16 + heading_y as i16).clamp(0, max) as u8;
+
+    Some(find_standable_tile((x, y)))
+}
+
+fn get_ids(creeps: &[Creep]) -> Vec<String> {
+    creeps
+        .iter()
+        .filter_map(|creep| creep.id().as_string())
+        .collect()
+}
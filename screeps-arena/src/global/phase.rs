@@ -0,0 +1,225 @@
+use std::collections::VecDeque;
+
+use super::{
+    debounce::{Debounced, Threshold},
+    strategy::get_strategy,
+};
+
+/// How many ticks of collected energy the income is averaged over.
+const INCOME_WINDOW: usize = 20;
+
+/// The stage the match is in, every arena goes through these in roughly this order.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Phase {
+    /// The first ticks, before the economy is running.
+    Opening,
+    /// Collecting energy and building an army while nobody is fighting.
+    BuildUp,
+    /// Enemies are at our home and we are fighting them off.
+    Engagement,
+    /// Our army is clearly stronger, so we go after the enemy.
+    Push,
+    /// The end of the match is close, everything goes for the win.
+    Endgame,
+}
+
+/// What the phase manager looks at every tick. Each arena fills it in from its own objects.
+pub struct PhaseSignals {
+    pub tick: u32,
+    /// Sum of the threat scores of our creeps.
+    pub army_strength: u32,
+    /// Sum of the threat scores of the enemy creeps.
+    pub enemy_strength: u32,
+    /// All the energy collected so far, used to work out the income. Arenas without an economy
+    /// pass 0, their opening then ends by time alone.
+    pub energy_collected: u32,
+    /// True when enemies that can do damage are close to our home.
+    pub enemies_near_home: bool,
+}
+
+type PhaseHook = Box<dyn FnMut(Phase, Phase, u32)>;
+
+/// Decides the phase of the match. A new phase has to be wanted for a few ticks in a row, and
+/// the current one has to have lasted a while, before the phase changes, so the bots don't flip
+/// back and forth when the signals sit right on a threshold.
+pub struct PhaseManager {
+    phase: Debounced<Phase>,
+    entered_at: u32,
+    endgame_tick: u32,
+    collected: VecDeque<u32>,
+    hooks: Vec<PhaseHook>,
+}
+
+impl PhaseManager {
+    /// The endgame starts at `endgame_tick` no matter what else is going on.
+    pub fn new(endgame_tick: u32) -> Self {
+        let strategy = &get_strategy().phases;
+
+        Self {
+            phase: Debounced::new(
+                Phase::Opening,
+                strategy.confirm_ticks,
+                strategy.min_phase_ticks,
+            ),
+            entered_at: 0,
+            endgame_tick,
+            collected: VecDeque::with_capacity(INCOME_WINDOW),
+            hooks: vec![],
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase.state()
+    }
+
+    /// The tick the current phase started.
+    pub fn entered_at(&self) -> u32 {
+        self.entered_at
+    }
+
+    /// Calls the hook with the old phase, the new phase and the tick every time the phase
+    /// changes. Spawners and squads use this to switch what they are doing.
+    pub fn on_change(mut self, hook: impl FnMut(Phase, Phase, u32) + 'static) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Average energy collected per tick over the last few ticks.
+    pub fn energy_income(&self) -> u32 {
+        match (self.collected.front(), self.collected.back()) {
+            (Some(oldest), Some(newest)) if self.collected.len() > 1 => {
+                newest.saturating_sub(*oldest) / (self.collected.len() as u32 - 1)
+            }
+            _ => 0,
+        }
+    }
+
+    /// Call once per tick. Returns the phase for this tick.
+    pub fn update(&mut self, signals: &PhaseSignals) -> Phase {
+        if self.collected.len() == INCOME_WINDOW {
+            self.collected.pop_front();
+        }
+        self.collected.push_back(signals.energy_collected);
+
+        let previous = self.phase();
+        let wanted = self.wanted_phase(signals);
+        // the endgame can't wait, everything else has to settle first
+        if wanted == Phase::Endgame {
+            self.phase.force(wanted);
+        } else {
+            self.phase.update(wanted);
+        }
+
+        let phase = self.phase();
+        if phase != previous {
+            self.entered_at = signals.tick;
+            for hook in self.hooks.iter_mut() {
+                hook(previous, phase, signals.tick);
+            }
+        }
+
+        phase
+    }
+
+    fn wanted_phase(&self, signals: &PhaseSignals) -> Phase {
+        let strategy = &get_strategy().phases;
+
+        if self.phase() == Phase::Endgame || signals.tick >= self.endgame_tick {
+            return Phase::Endgame;
+        }
+
+        if signals.enemies_near_home {
+            return Phase::Engagement;
+        }
+
+        // pushing starts at a higher ratio than it stops at, so a single lost creep doesn't
+        // call the push off
+        let enemy_strength = signals.enemy_strength as f64;
+        let push_threshold = Threshold::new(
+            enemy_strength * strategy.start_push_ratio,
+            enemy_strength * strategy.hold_push_ratio,
+        );
+        if signals.army_strength >= strategy.min_push_strength
+            && push_threshold.is_above(signals.army_strength as f64, self.phase() == Phase::Push)
+        {
+            return Phase::Push;
+        }
+
+        if self.phase() == Phase::Opening
+            && signals.tick < strategy.opening_ticks
+            && self.energy_income() < strategy.build_up_income
+        {
+            return Phase::Opening;
+        }
+
+        Phase::BuildUp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    fn quiet_tick(tick: u32) -> PhaseSignals {
+        PhaseSignals {
+            tick,
+            army_strength: 0,
+            enemy_strength: 0,
+            energy_collected: 0,
+            enemies_near_home: false,
+        }
+    }
+
+    #[test]
+    fn income_is_averaged_over_the_window() {
+        let mut phases = PhaseManager::new(u32::MAX);
+        for tick in 0..3 {
+            phases.update(&PhaseSignals {
+                energy_collected: tick * 10,
+                ..quiet_tick(tick)
+            });
+        }
+
+        assert_eq!(phases.energy_income(), 10);
+    }
+
+    #[test]
+    fn enemies_at_home_start_an_engagement_once_confirmed() {
+        let strategy = &get_strategy().phases;
+        let ticks_to_switch = strategy.confirm_ticks.max(strategy.min_phase_ticks);
+        let mut phases = PhaseManager::new(u32::MAX);
+
+        for tick in 1..ticks_to_switch {
+            let phase = phases.update(&PhaseSignals {
+                enemies_near_home: true,
+                ..quiet_tick(tick)
+            });
+            assert_eq!(phase, Phase::Opening);
+        }
+        let phase = phases.update(&PhaseSignals {
+            enemies_near_home: true,
+            ..quiet_tick(ticks_to_switch)
+        });
+
+        assert_eq!(phase, Phase::Engagement);
+        assert_eq!(phases.entered_at(), ticks_to_switch);
+    }
+
+    #[test]
+    fn the_endgame_starts_right_away_and_calls_the_hooks() {
+        let changes = Rc::new(RefCell::new(vec![]));
+        let recorded = changes.clone();
+        let mut phases = PhaseManager::new(10)
+            .on_change(move |from, to, tick| recorded.borrow_mut().push((from, to, tick)));
+
+        assert_eq!(phases.update(&quiet_tick(9)), Phase::Opening);
+        assert_eq!(phases.update(&quiet_tick(10)), Phase::Endgame);
+        assert_eq!(phases.update(&quiet_tick(11)), Phase::Endgame);
+        assert_eq!(
+            *changes.borrow(),
+            vec![(Phase::Opening, Phase::Endgame, 10)]
+        );
+    }
+}
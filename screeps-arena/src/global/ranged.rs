@@ -0,0 +1,141 @@
+use screeps_arena::Creep;
+
+use super::classifier::{classify, pick_target, ThreatScore};
+
+/// Furthest a ranged attack or mass attack reaches.
+const RANGED_RANGE: u8 = 3;
+
+#[derive(Clone, Copy, Debug)]
+pub enum RangedAttack<'a> {
+    Single(&'a Creep),
+    Mass,
+    /// Nothing in range, or the creep has no working ranged attack parts.
+    Nothing,
+}
+
+/// Picks the ranged attack that does the most damage this tick. A mass attack hits every enemy
+/// within 3 tiles for 10, 4 or 1 per part depending on the range while a single target attack
+/// hits one for 10, so the mass attack wins once enough enemies are packed close. Mass attacks
+/// never hurt our own creeps so they don't limit it. Damage past what kills a creep is wasted and
+/// isn't counted. The single target is `target` when it is in range, the enemy `pick_target`
+/// likes best otherwise. Ties go to the single target so the damage stays focused.
+pub fn choose_ranged_attack<'a>(
+    creep: &Creep,
+    target: Option<&'a Creep>,
+    enemies: &'a [Creep],
+) -> RangedAttack<'a> {
+    let threat = classify(creep).threat;
+    if threat.ranged_dps[0] == 0 {
+        return RangedAttack::Nothing;
+    }
+
+    let in_range = enemies
+        .iter()
+        .filter(|enemy| creep.get_range_to(*enemy) <= RANGED_RANGE)
+        .cloned()
+        .collect::<Vec<Creep>>();
+    let single_target = match target {
+        Some(target) if creep.get_range_to(target) <= RANGED_RANGE => Some(target),
+        _ => pick_target(creep, &in_range)
+            .and_then(|picked| enemies.iter().find(|enemy| enemy.id() == picked.id())),
+    };
+
+    let (single_damage, mass_damage) = get_attack_damage(
+        &threat,
+        single_target.map(|target| (creep.get_range_to(target), target.hits())),
+        &in_range
+            .iter()
+            .map(|enemy| (creep.get_range_to(enemy), enemy.hits()))
+            .collect::<Vec<(u8, u32)>>(),
+    );
+
+    match single_target {
+        Some(target) if single_damage >= mass_damage => RangedAttack::Single(target),
+        _ if mass_damage > 0 => RangedAttack::Mass,
+        _ => RangedAttack::Nothing,
+    }
+}
+
+/// Damage of the single target attack and of the mass attack, from the range and hits of the
+/// single target and of every enemy in range. Damage past what kills a creep isn't counted.
+fn get_attack_damage(
+    threat: &ThreatScore,
+    single_target: Option<(u8, u32)>,
+    in_range: &[(u8, u32)],
+) -> (u32, u32) {
+    let single_damage = single_target.map_or(0, |(range, hits)| {
+        threat.ranged_dps[range.clamp(1, RANGED_RANGE) as usize - 1].min(hits)
+    });
+    let mass_damage = in_range
+        .iter()
+        .map(|(range, hits)| {
+            threat.mass_attack_dps[(*range).clamp(1, RANGED_RANGE) as usize - 1].min(*hits)
+        })
+        .sum();
+
+    (single_damage, mass_damage)
+}
+
+/// Issues the ranged attack `choose_ranged_attack` picks. Returns false when nothing was fired.
+pub fn fire_ranged(creep: &Creep, target: Option<&Creep>, enemies: &[Creep]) -> bool {
+    match choose_ranged_attack(creep, target, enemies) {
+        RangedAttack::Single(target) => {
+            creep.ranged_attack(target);
+            true
+        }
+        RangedAttack::Mass => {
+            creep.ranged_mass_attack();
+            true
+        }
+        RangedAttack::Nothing => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranged_threat(ranged_attacks: u32) -> ThreatScore {
+        ThreatScore {
+            ranged_dps: [10 * ranged_attacks; 3],
+            mass_attack_dps: [10, 4, 1].map(|power| power * ranged_attacks),
+            ..ThreatScore::default()
+        }
+    }
+
+    #[test]
+    fn a_lone_enemy_next_to_us_takes_the_same_from_both() {
+        let damage = get_attack_damage(&ranged_threat(1), Some((1, 100)), &[(1, 100)]);
+
+        assert_eq!(damage, (10, 10));
+    }
+
+    #[test]
+    fn packed_enemies_take_more_from_a_mass_attack() {
+        let in_range = [(1, 100), (1, 100), (2, 100)];
+        let damage = get_attack_damage(&ranged_threat(2), Some((1, 100)), &in_range);
+
+        assert_eq!(damage, (20, 48));
+    }
+
+    #[test]
+    fn spread_out_enemies_take_more_from_a_single_attack() {
+        let in_range = [(3, 100), (3, 100), (3, 100)];
+        let damage = get_attack_damage(&ranged_threat(1), Some((3, 100)), &in_range);
+
+        assert_eq!(damage, (10, 3));
+    }
+
+    #[test]
+    fn overkill_is_not_counted() {
+        let in_range = [(1, 5), (1, 3)];
+        let damage = get_attack_damage(&ranged_threat(1), Some((1, 5)), &in_range);
+
+        assert_eq!(damage, (5, 8));
+    }
+
+    #[test]
+    fn nothing_to_shoot_does_no_damage() {
+        assert_eq!(get_attack_damage(&ranged_threat(1), None, &[]), (0, 0));
+    }
+}
@@ -0,0 +1,112 @@
+use eyre::{bail, Result};
+use js_sys::Reflect;
+use screeps_arena::Creep;
+use wasm_bindgen::JsValue;
+
+use super::{
+    classifier::PartCounts,
+    debounce::Threshold,
+    formation::find_standable_tile,
+    healing::predict_incoming_damage,
+    influence::InfluenceMap,
+    strategy::get_strategy,
+    terrain::get_range,
+    traffic::{TrafficManager, RETREAT_PRIORITY},
+    utilities::create_position_object,
+};
+
+const RETREATING_KEY: &str = "retreating";
+/// How far around the rally point we look for a tile the enemy can't hit.
+const RALLY_SEARCH_RANGE: u8 = 3;
+
+/// Decides whether the creep should fall back this tick and remembers it on the creep. A creep
+/// retreats when it lost too many of its combat parts, when the damage predicted for next tick
+/// would kill it, or when the damage would leave it below the retreat hits. It keeps retreating
+/// until it is healed back above the re-engage hits and is no longer about to die.
+pub fn should_retreat(creep: &Creep, enemies: &[Creep]) -> Result<bool> {
+    let strategy = &get_strategy().retreat;
+    let is_retreating = is_retreating(creep)?;
+    let predicted_damage = predict_incoming_damage(creep, enemies);
+    let would_die = predicted_damage >= creep.hits();
+
+    let hits_after_damage = creep.hits().saturating_sub(predicted_damage);
+    let hits_ratio = hits_after_damage as f64 / creep.hits_max().max(1) as f64;
+    let is_low_on_hits = Threshold::new(strategy.retreat_hits_ratio, strategy.reengage_hits_ratio)
+        .is_below(hits_ratio, is_retreating);
+    let is_disarmed = get_combat_parts_ratio(creep) < strategy.min_combat_parts_ratio;
+
+    let should_retreat = would_die || is_low_on_hits || is_disarmed;
+    if should_retreat != is_retreating {
+        set_retreating(creep, should_retreat)?;
+    }
+
+    Ok(should_retreat)
+}
+
+/// Where beaten creeps gather, `rally_distance` tiles from home toward the enemy but never past
+/// the middle, on the tile close by that takes the least damage.
+pub fn get_rally_point(home: (u8, u8), enemy_home: (u8, u8), influence: &InfluenceMap) -> (u8, u8) {
+    let distance = get_strategy()
+        .retreat
+        .rally_distance
+        .min(get_range(home, enemy_home) / 2) as i16;
+    let step = |from: u8, to: u8| {
+        let offset = (to as i16 - from as i16).clamp(-distance, distance);
+        (from as i16 + offset) as u8
+    };
+    let rally_point = find_standable_tile((step(home.0, enemy_home.0), step(home.1, enemy_home.1)));
+
+    influence.safest_position_near(rally_point.0, rally_point.1, RALLY_SEARCH_RANGE)
+}
+
+/// Falls back to the rally point around the enemy damage, healing itself on the way.
+pub fn retreat_to(
+    creep: &Creep,
+    rally_point: (u8, u8),
+    influence: &InfluenceMap,
+    traffic: &mut TrafficManager,
+) -> Result<()> {
+    #[cfg(feature = "debug-visuals")]
+    crate::global::visuals::draw_role_label(creep, "retreating");
+
+    if creep.hits() < creep.hits_max() {
+        creep.heal(creep);
+    }
+    traffic.request_move_toward(
+        creep,
+        &create_position_object(rally_point.0, rally_point.1),
+        Some(&influence.move_options(creep.hits())),
+        RETREAT_PRIORITY,
+    )
+}
+
+/// Share of the creep's attack, ranged attack and heal parts that still work. Creeps without any
+/// count as fully armed, they never fought in the first place.
+fn get_combat_parts_ratio(creep: &Creep) -> f64 {
+    let total = PartCounts::spawned_with(creep).combat();
+    if total == 0 {
+        return 1.0;
+    }
+
+    PartCounts::from_creep(creep).combat() as f64 / total as f64
+}
+
+/// Whether `should_retreat` last sent the creep back.
+pub fn is_retreating(creep: &Creep) -> Result<bool> {
+    match Reflect::get(creep, &JsValue::from_str(RETREATING_KEY)) {
+        Ok(retreating) => Ok(retreating.as_bool().unwrap_or_default()),
+        Err(_) => bail!("Error getting retreating from creep"),
+    }
+}
+
+fn set_retreating(creep: &Creep, retreating: bool) -> Result<()> {
+    if let Err(error) = Reflect::set(
+        creep,
+        &JsValue::from_str(RETREATING_KEY),
+        &JsValue::from_bool(retreating),
+    ) {
+        bail!("Error setting retreating on creep: {:?}", error);
+    }
+
+    Ok(())
+}
@@ -1,102 +1,164 @@
+use std::collections::HashMap;
+
+use eyre::{bail, Result};
 use js_sys::Reflect;
-use log::warn;
-use screeps_arena::{Creep, Part};
+use screeps_arena::{Creep, Part, StructureSpawn};
 use wasm_bindgen::JsValue;
 
-#[derive(PartialEq)]
-pub enum Role {
-    Defender,
-    Attacker,
-    Healer,
-    Collector,
-    Unknown,
+use super::{stats, utilities::log_creep_error};
+
+const ROLE_KEY: &str = "role";
+
+/// A job a creep can have. Each arena registers the roles it uses in a `RoleRegistry` together
+/// with a `Context` holding whatever the roles need to look at this tick.
+pub trait RoleBehavior {
+    type Context;
+
+    /// Stored on the creep to find its role again on later ticks, must be unique per registry.
+    fn name(&self) -> &'static str;
+
+    fn body(&self) -> Vec<Part>;
+
+    fn run(&self, creep: &Creep, context: &Self::Context) -> Result<()>;
+
+    /// When several roles are below their wanted count the highest priority is spawned first.
+    fn spawn_priority(&self) -> u8;
+
+    fn wanted(&self, _context: &Self::Context) -> u8 {
+        0
+    }
 }
 
-#[allow(dead_code)]
-impl Role {
-    pub fn attach_to_creep(self, creep: &Creep) {
-        if let Err(error) = Reflect::set(creep, &JsValue::from_str("role"), &self.js_value()) {
-            warn!("error attaching role to creep: {:?}", error);
-            panic!();
+pub struct RoleRegistry<C> {
+    roles: Vec<Box<dyn RoleBehavior<Context = C>>>,
+}
+
+impl<C> RoleRegistry<C> {
+    pub fn new() -> Self {
+        Self { roles: vec![] }
+    }
+
+    pub fn register(mut self, role: impl RoleBehavior<Context = C> + 'static) -> Self {
+        self.roles.push(Box::new(role));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn RoleBehavior<Context = C>> {
+        self.roles
+            .iter()
+            .find(|role| role.name() == name)
+            .map(|role| role.as_ref())
+    }
+
+    /// The registered role of the creep, `None` if the creep hasn't been given one yet.
+    pub fn role_of(&self, creep: &Creep) -> Result<Option<&dyn RoleBehavior<Context = C>>> {
+        match get_role_name(creep)? {
+            Some(name) => match self.get(&name) {
+                Some(role) => Ok(Some(role)),
+                None => bail!("role {name} is not registered"),
+            },
+            None => Ok(None),
         }
     }
 
-    pub fn create_body_for_role(&self) -> Vec<Part> {
-        match self {
-            Role::Defender => {
-                vec![
-                    Part::Attack,
-                    Part::Attack,
-                    Part::Attack,
-                    Part::RangedAttack,
-                    Part::RangedAttack,
-                    Part::RangedAttack,
-                    Part::Move,
-                    Part::Move,
-                    Part::Move,
-                ]
-            }
-            Role::Attacker => vec![
-                Part::Attack,
-                Part::Attack,
-                Part::RangedAttack,
-                Part::RangedAttack,
-                Part::Move,
-                Part::Move,
-                Part::Move,
-                Part::Move,
-                Part::Move,
-                Part::Move,
-                Part::Move,
-                Part::Move,
-                Part::Move,
-            ],
-            Role::Healer => vec![
-                Part::Heal,
-                Part::Heal,
-                Part::Move,
-                Part::Move,
-                Part::Move,
-                Part::Move,
-                Part::Move,
-                Part::Move,
-                Part::Move,
-            ],
-            Role::Collector => vec![Part::Carry, Part::Move],
-            Role::Unknown => {
-                warn!("unknown role");
-                panic!();
+    pub fn is_role(&self, creep: &Creep, name: &str) -> bool {
+        matches!(get_role_name(creep), Ok(Some(role_name)) if role_name == name)
+    }
+
+    pub fn count_roles(&self, creeps: &[Creep]) -> HashMap<&'static str, u8> {
+        let mut counts = self
+            .roles
+            .iter()
+            .map(|role| (role.name(), 0))
+            .collect::<HashMap<&'static str, u8>>();
+
+        for creep in creeps {
+            if let Ok(Some(role)) = self.role_of(creep) {
+                *counts.entry(role.name()).or_insert(0) += 1;
             }
         }
+
+        counts
     }
 
-    fn js_value(&self) -> JsValue {
-        JsValue::from_str(match self {
-            Role::Defender => "defender",
-            Role::Attacker => "attacker",
-            Role::Healer => "healer",
-            Role::Collector => "collector",
-            Role::Unknown => "unknown",
-        })
+    /// The highest priority role that has fewer creeps than it wants.
+    pub fn needed_role(
+        &self,
+        counts: &HashMap<&'static str, u8>,
+        context: &C,
+    ) -> Option<&dyn RoleBehavior<Context = C>> {
+        self.roles
+            .iter()
+            .filter(|role| counts.get(role.name()).copied().unwrap_or(0) < role.wanted(context))
+            .max_by_key(|role| role.spawn_priority())
+            .map(|role| role.as_ref())
     }
-}
 
-impl From<&Creep> for Role {
-    fn from(creep: &Creep) -> Self {
-        let role_string = match Reflect::get(creep, &JsValue::from_str("role")) {
-            Ok(role) => role.as_string().unwrap(),
-            Err(_) => {
-                warn!("Error getting role from creep");
-                panic!();
+    /// Spawns a creep for the role, returning it if the spawn was able to start it this tick.
+    pub fn spawn(
+        &self,
+        spawn: &StructureSpawn,
+        role: &dyn RoleBehavior<Context = C>,
+    ) -> Result<Option<Creep>> {
+        match spawn.spawn_creep(&role.body()) {
+            Ok(creep) => {
+                attach_role(&creep, role.name())?;
+                stats::record_spawn(role.name());
+                Ok(Some(creep))
             }
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Spawns the most needed role, if any, and counts it straight away.
+    pub fn spawn_needed(
+        &self,
+        spawn: &StructureSpawn,
+        counts: &mut HashMap<&'static str, u8>,
+        context: &C,
+    ) -> Result<Option<Creep>> {
+        let role = match self.needed_role(counts, context) {
+            Some(role) => role,
+            None => return Ok(None),
         };
+        let creep = self.spawn(spawn, role)?;
+
+        if creep.is_some() {
+            *counts.entry(role.name()).or_insert(0) += 1;
+        }
 
-        match role_string.as_str() {
-            "defender" => Self::Defender,
-            "attacker" => Self::Attacker,
-            "healer" => Self::Healer,
-            "collector" => Self::Collector,
-            _ => Self::Unknown,
+        Ok(creep)
+    }
+
+    /// Runs the role of every creep. A creep that errors is logged and skipped so the rest still
+    /// act this tick, creeps without a role are left alone.
+    pub fn run_creeps(&self, creeps: &[Creep], context: &C) {
+        for creep in creeps {
+            let result = match self.role_of(creep) {
+                Ok(Some(role)) => role.run(creep, context),
+                Ok(None) => Ok(()),
+                Err(error) => Err(error),
+            };
+            log_creep_error(creep, result);
         }
     }
 }
+
+pub fn attach_role(creep: &Creep, name: &str) -> Result<()> {
+    if let Err(error) = Reflect::set(
+        creep,
+        &JsValue::from_str(ROLE_KEY),
+        &JsValue::from_str(name),
+    ) {
+        bail!("error attaching role to creep: {:?}", error);
+    }
+
+    Ok(())
+}
+
+pub fn get_role_name(creep: &Creep) -> Result<Option<String>> {
+    match Reflect::get(creep, &JsValue::from_str(ROLE_KEY)) {
+        Ok(role) => Ok(role.as_string()),
+        Err(_) => bail!("Error getting role from creep"),
+    }
+}
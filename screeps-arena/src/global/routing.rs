@@ -0,0 +1,175 @@
+use eyre::{bail, Result};
+use js_sys::{Object, Reflect};
+use screeps_arena::{Creep, ResourceType, StructureContainer, StructureSpawn};
+use wasm_bindgen::JsValue;
+
+use super::{
+    body::CreepStats,
+    classifier::classify,
+    influence::InfluenceMap,
+    movement::estimate_arrival,
+    strategy::get_strategy,
+    traffic::{TrafficManager, RETREAT_PRIORITY},
+    utilities::get_position,
+};
+
+const TARGET_CONTAINER_KEY: &str = "target_container";
+/// How much energy a point of expected damage along the path is worth.
+const DAMAGE_WEIGHT: f64 = 2.0;
+/// Only this many of the containers closest to the collector get a path worked out, pathfinding
+/// to every container for every collector each tick costs too much CPU.
+const MAX_CANDIDATES: usize = 4;
+
+/// Picks the container a collector should empty next out of the closest ones holding energy.
+/// Containers are scored by the energy the collector can expect to get, shared with the
+/// collectors already heading there, divided by the ticks it takes to walk there and reduced by
+/// the damage waiting along the path. Containers behind a tile that would kill the collector are
+/// skipped.
+pub fn choose_container<'a>(
+    creep: &Creep,
+    containers: &'a [StructureContainer],
+    other_collectors: &[Creep],
+    influence: &InfluenceMap,
+) -> Result<Option<&'a StructureContainer>> {
+    // forget a target that was emptied or destroyed so the other collectors stop making room
+    // for us there
+    if let Some(target_id) = get_target_container(creep)? {
+        let is_available = containers.iter().any(|container| {
+            container.id().as_string().as_deref() == Some(target_id.as_str())
+                && container
+                    .store()
+                    .get_used_capacity(Some(ResourceType::Energy))
+                    > 0
+        });
+        if !is_available {
+            clear_target_container(creep)?;
+        }
+    }
+
+    let capacity = creep.store().get_free_capacity(Some(ResourceType::Energy));
+    let move_options = influence.move_options(creep.hits());
+    let stats = CreepStats::from_creep(creep);
+    let mut best = None;
+    let mut best_score = f64::MIN;
+
+    let mut candidates = containers
+        .iter()
+        .filter(|container| {
+            container
+                .store()
+                .get_used_capacity(Some(ResourceType::Energy))
+                > 0
+        })
+        .collect::<Vec<&StructureContainer>>();
+    candidates.sort_by_key(|container| creep.get_range_to(container));
+    candidates.truncate(MAX_CANDIDATES);
+
+    for container in candidates {
+        let container_id = match container.id().as_string() {
+            Some(container_id) => container_id,
+            None => continue,
+        };
+        let energy = container
+            .store()
+            .get_used_capacity(Some(ResourceType::Energy));
+
+        let contention = other_collectors
+            .iter()
+            .filter(|collector| collector.id() != creep.id())
+            .filter(|collector| {
+                matches!(get_target_container(collector), Ok(Some(id)) if id == container_id)
+            })
+            .count();
+
+        let path = creep.find_path_to(container, Some(&move_options));
+        let mut positions = Vec::with_capacity(path.length() as usize);
+        let mut path_damage = 0;
+        let mut is_lethal = false;
+        for step in path.iter() {
+            let (x, y) = get_position(&Object::from(step))?;
+            positions.push((x, y));
+            path_damage += influence.net_damage_at(x, y);
+            is_lethal |= influence.is_lethal(x, y, creep.hits());
+        }
+        if is_lethal {
+            continue;
+        }
+        let arrival_ticks = match estimate_arrival(&stats, &positions) {
+            Some(arrival_ticks) => arrival_ticks,
+            None => continue,
+        };
+
+        let expected_energy = energy.min(capacity) as f64 / (contention + 1) as f64;
+        let score =
+            expected_energy / (arrival_ticks + 1) as f64 - path_damage as f64 * DAMAGE_WEIGHT;
+        if score > best_score {
+            best = Some(container);
+            best_score = score;
+        }
+    }
+
+    match best {
+        Some(container) => set_target_container(creep, container)?,
+        None => clear_target_container(creep)?,
+    }
+
+    Ok(best)
+}
+
+/// True when an enemy that can do damage is within the strategy's flee radius of the collector.
+pub fn should_flee(creep: &Creep, enemies: &[Creep]) -> bool {
+    let flee_radius = get_strategy().spawn_and_swamp.collector_flee_radius;
+
+    enemies
+        .iter()
+        .any(|enemy| classify(enemy).threat.score() > 0 && creep.get_range_to(enemy) <= flee_radius)
+}
+
+/// Drops back toward our spawn, walking around the enemy damage on the way.
+pub fn flee_to_spawn(
+    creep: &Creep,
+    spawn: &StructureSpawn,
+    influence: &InfluenceMap,
+    traffic: &mut TrafficManager,
+) -> Result<()> {
+    #[cfg(feature = "debug-visuals")]
+    crate::global::visuals::draw_role_label(creep, "fleeing");
+
+    traffic.request_move_toward(
+        creep,
+        spawn,
+        Some(&influence.move_options(creep.hits())),
+        RETREAT_PRIORITY,
+    )
+}
+
+fn get_target_container(creep: &Creep) -> Result<Option<String>> {
+    match Reflect::get(creep, &JsValue::from_str(TARGET_CONTAINER_KEY)) {
+        Ok(container_id) => Ok(container_id.as_string()),
+        Err(_) => bail!("Error getting target container from creep"),
+    }
+}
+
+fn clear_target_container(creep: &Creep) -> Result<()> {
+    if let Err(error) = Reflect::set(
+        creep,
+        &JsValue::from_str(TARGET_CONTAINER_KEY),
+        &JsValue::null(),
+    ) {
+        bail!("Error clearing target container from creep: {:?}", error);
+    }
+
+    Ok(())
+}
+
+fn set_target_container(creep: &Creep, container: &StructureContainer) -> Result<()> {
+    if let Err(error) = Reflect::set(
+        creep,
+        &JsValue::from_str(TARGET_CONTAINER_KEY),
+        &container.id(),
+    ) {
+        bail!("Error setting target container on creep: {:?}", error);
+    }
+
+    Ok(())
+}
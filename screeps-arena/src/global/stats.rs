@@ -0,0 +1,152 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
+
+use log::{info, warn};
+use screeps_arena::Creep;
+use serde::Serialize;
+
+use super::utilities::get_creeps;
+
+/// Every arena we play is limited to 2000 ticks. A match can end earlier when a spawn or flag is
+/// lost, which is why the report can also be printed periodically.
+pub const MATCH_TICK_LIMIT: u32 = 2000;
+pub const PERIODIC_REPORT_INTERVAL: u32 = 250;
+
+thread_local! {
+    pub static STATS: Rc<RefCell<Stats>> = Rc::new(RefCell::new(Stats::default()));
+}
+
+#[derive(Serialize, Default)]
+pub struct Stats {
+    pub tick: u32,
+    pub spawned: BTreeMap<String, u32>,
+    pub creeps_lost: u32,
+    pub enemies_killed: u32,
+    pub damage_dealt: u32,
+    pub damage_taken: u32,
+    pub healing_done: u32,
+    pub energy_withdrawn: u32,
+    pub energy_delivered: u32,
+    pub phases: Vec<(String, u32)>,
+    #[serde(skip)]
+    my_hits: HashMap<String, u32>,
+    #[serde(skip)]
+    enemy_hits: HashMap<String, u32>,
+}
+
+impl Stats {
+    /// Compares the hits of every creep with the previous tick. Creeps that disappeared are
+    /// counted as dead with their remaining hits added to the damage.
+    fn track_hits(&mut self, my_creeps: &[Creep], enemies: &[Creep]) {
+        let (damage_taken, healing_done, creeps_lost) = diff_hits(&mut self.my_hits, my_creeps);
+        self.damage_taken += damage_taken;
+        self.healing_done += healing_done;
+        self.creeps_lost += creeps_lost;
+
+        let (damage_dealt, _enemy_healing, enemies_killed) =
+            diff_hits(&mut self.enemy_hits, enemies);
+        self.damage_dealt += damage_dealt;
+        self.enemies_killed += enemies_killed;
+    }
+
+    fn report(&self) {
+        match serde_json::to_string(self) {
+            Ok(json) => info!("stats: {json}"),
+            Err(error) => warn!("Error serializing stats: {:?}", error),
+        }
+    }
+}
+
+/// Returns the damage, healing and deaths since the last time this was called with the same
+/// `previous_hits`, then replaces it with the current hits.
+fn diff_hits(previous_hits: &mut HashMap<String, u32>, creeps: &[Creep]) -> (u32, u32, u32) {
+    let mut damage = 0;
+    let mut healing = 0;
+    let mut current_hits = HashMap::new();
+
+    for creep in creeps {
+        let id = match creep.id().as_string() {
+            Some(id) => id,
+            None => continue,
+        };
+        let hits = creep.hits();
+
+        if let Some(last_hits) = previous_hits.remove(&id) {
+            if hits < last_hits {
+                damage += last_hits - hits;
+            } else {
+                healing += hits - last_hits;
+            }
+        }
+
+        current_hits.insert(id, hits);
+    }
+
+    let deaths = previous_hits.len() as u32;
+    damage += previous_hits.values().sum::<u32>();
+    *previous_hits = current_hits;
+
+    (damage, healing, deaths)
+}
+
+/// Call once at the end of every tick, after all of the bots have run. The final report is
+/// printed by `report` when the match ends.
+pub fn update(tick: u32, report_periodically: bool) {
+    let my_creeps = get_creeps(true);
+    let enemies = get_creeps(false);
+
+    STATS.with(|stats| {
+        let mut stats = stats.borrow_mut();
+        stats.tick = tick;
+        stats.track_hits(&my_creeps, &enemies);
+
+        if report_periodically && tick % PERIODIC_REPORT_INTERVAL == 0 {
+            stats.report();
+        }
+    });
+}
+
+pub fn report() {
+    STATS.with(|stats| stats.borrow().report());
+}
+
+pub fn record_spawn(role: &str) {
+    STATS.with(|stats| {
+        *stats
+            .borrow_mut()
+            .spawned
+            .entry(role.to_owned())
+            .or_insert(0) += 1;
+    });
+}
+
+pub fn record_energy_withdrawn(amount: u32) {
+    STATS.with(|stats| stats.borrow_mut().energy_withdrawn += amount);
+}
+
+pub fn record_energy_delivered(amount: u32) {
+    STATS.with(|stats| stats.borrow_mut().energy_delivered += amount);
+}
+
+pub fn get_energy_delivered() -> u32 {
+    STATS.with(|stats| stats.borrow().energy_delivered)
+}
+
+/// Stores the tick a phase began. Calling this again with the current phase does nothing, so it
+/// is safe to call every tick.
+pub fn record_phase(phase: &str, tick: u32) {
+    STATS.with(|stats| {
+        let mut stats = stats.borrow_mut();
+        let is_new_phase = stats
+            .phases
+            .last()
+            .map_or(true, |(current_phase, _)| current_phase != phase);
+
+        if is_new_phase {
+            stats.phases.push((phase.to_owned(), tick));
+        }
+    });
+}
@@ -0,0 +1,341 @@
+use std::rc::Rc;
+
+use eyre::{bail, Result};
+use log::warn;
+use serde::Deserialize;
+
+/// The strategy file picked by `build.rs`, see `SCREEPS_STRATEGY`.
+const STRATEGY_JSON: &str = include_str!(concat!(env!("OUT_DIR"), "/strategy.json"));
+
+thread_local! {
+    static STRATEGY: Rc<Strategy> = Rc::new(Strategy::load(STRATEGY_JSON));
+}
+
+/// Returns the strategy that was embedded at build time.
+pub fn get_strategy() -> Rc<Strategy> {
+    STRATEGY.with(|strategy| strategy.clone())
+}
+
+/// Tuning values for every arena. Anything missing from the strategy file falls back to the
+/// defaults, so a variant only has to list what it changes.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Strategy {
+    pub capture_the_flag: CaptureTheFlagStrategy,
+    pub spawn_and_swamp: SpawnAndSwampStrategy,
+    pub final_test: FinalTestStrategy,
+    pub movement: MovementStrategy,
+    pub phases: PhaseStrategy,
+    pub towers: TowerStrategy,
+    pub retreat: RetreatStrategy,
+}
+
+impl Strategy {
+    /// Parses and validates the strategy, logging the problem and using the defaults if either
+    /// step fails.
+    fn load(json: &str) -> Self {
+        let strategy = match serde_json::from_str::<Self>(json) {
+            Ok(strategy) => strategy,
+            Err(error) => {
+                warn!("Error parsing strategy, using defaults: {:?}", error);
+                return Self::default();
+            }
+        };
+
+        if let Err(error) = strategy.validate() {
+            warn!("Invalid strategy, using defaults: {:?}", error);
+            return Self::default();
+        }
+
+        strategy
+    }
+
+    fn validate(&self) -> Result<()> {
+        self.capture_the_flag.validate()?;
+        self.spawn_and_swamp.validate()?;
+        self.final_test.validate()?;
+        self.movement.validate()?;
+        self.phases.validate()?;
+        self.towers.validate()?;
+        self.retreat.validate()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct CaptureTheFlagStrategy {
+    /// Once this tick is reached we assume the enemy is turtling and go for their flag.
+    pub turtle_tick: u32,
+    /// Enemies closer than this to our flag are on our side of the map.
+    pub our_side_distance: u8,
+    /// Enemies closer than this to our flag, but not on our side, are in the river.
+    pub river_distance: u8,
+    /// Towers fire at enemies this close to our flag even when they are low on energy.
+    pub tower_range: u8,
+    /// How much further an enemy has to go back out of our side or the river than it had to go
+    /// in before we change the state.
+    pub state_margin: u8,
+    /// A new enemy state has to be seen for this many ticks in a row before we switch to it.
+    pub state_confirm_ticks: u32,
+    /// An enemy state lasts at least this many ticks before we switch away from it.
+    pub state_min_dwell_ticks: u32,
+}
+
+impl CaptureTheFlagStrategy {
+    fn validate(&self) -> Result<()> {
+        if self.our_side_distance >= self.river_distance {
+            bail!("capture_the_flag.our_side_distance must be less than river_distance");
+        }
+
+        if self.turtle_tick == 0 {
+            bail!("capture_the_flag.turtle_tick must be greater than 0");
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for CaptureTheFlagStrategy {
+    fn default() -> Self {
+        Self {
+            turtle_tick: 1500,
+            our_side_distance: 5,
+            river_distance: 75,
+            tower_range: 5,
+            state_margin: 5,
+            state_confirm_ticks: 3,
+            state_min_dwell_ticks: 10,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct SpawnAndSwampStrategy {
+    pub want_collectors: u8,
+    pub want_attackers: u8,
+    pub want_healers: u8,
+    /// Attackers wait at the staging area until this many of them exist.
+    pub group_size_before_attack: u8,
+    /// Defenders engage enemies within this range of our spawn.
+    pub defender_threat_range: u8,
+    /// Collectors drop back to the spawn when an enemy that can do damage is this close.
+    pub collector_flee_radius: u8,
+    /// The endgame phase starts at this tick.
+    pub endgame_tick: u32,
+}
+
+impl SpawnAndSwampStrategy {
+    fn validate(&self) -> Result<()> {
+        if self.group_size_before_attack == 0 {
+            bail!("spawn_and_swamp.group_size_before_attack must be greater than 0");
+        }
+
+        if self.defender_threat_range == 0 || self.collector_flee_radius == 0 {
+            bail!("spawn_and_swamp threat ranges must be greater than 0");
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for SpawnAndSwampStrategy {
+    fn default() -> Self {
+        Self {
+            want_collectors: 1,
+            want_attackers: 100,
+            want_healers: 2,
+            group_size_before_attack: 8,
+            defender_threat_range: 10,
+            collector_flee_radius: 6,
+            endgame_tick: 1800,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct FinalTestStrategy {
+    pub desired_spawn_refiller_count: u8,
+    pub desired_fighter_count: u8,
+    pub desired_ranger_count: u8,
+    /// Healers are paired with the fighters and rangers and follow them around.
+    pub desired_healer_count: u8,
+    /// How many tower refillers we want while a tower is below the refill threshold.
+    pub desired_tower_refiller_count: u8,
+    /// Towers with less energy than this get refilled.
+    pub tower_refill_threshold: u32,
+}
+
+impl FinalTestStrategy {
+    fn validate(&self) -> Result<()> {
+        if self.desired_spawn_refiller_count == 0 {
+            bail!("final_test.desired_spawn_refiller_count must be greater than 0");
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for FinalTestStrategy {
+    fn default() -> Self {
+        Self {
+            desired_spawn_refiller_count: 2,
+            desired_fighter_count: 1,
+            desired_ranger_count: 5,
+            desired_healer_count: 2,
+            desired_tower_refiller_count: 1,
+            tower_refill_threshold: 30,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct MovementStrategy {
+    /// A creep that hasn't moved for this many ticks, without being fatigued, paths around our
+    /// other creeps.
+    pub stalled_ticks_before_repath: u8,
+    /// A creep that hasn't moved for this many ticks is reported as stuck so its task can be
+    /// reassigned.
+    pub stalled_ticks_before_escalation: u8,
+}
+
+impl MovementStrategy {
+    fn validate(&self) -> Result<()> {
+        if self.stalled_ticks_before_repath == 0 {
+            bail!("movement.stalled_ticks_before_repath must be greater than 0");
+        }
+
+        if self.stalled_ticks_before_escalation <= self.stalled_ticks_before_repath {
+            bail!("movement.stalled_ticks_before_escalation must be greater than stalled_ticks_before_repath");
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for MovementStrategy {
+    fn default() -> Self {
+        Self {
+            stalled_ticks_before_repath: 2,
+            stalled_ticks_before_escalation: 10,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct PhaseStrategy {
+    /// The opening ends after this many ticks even if the income is still low.
+    pub opening_ticks: u32,
+    /// The opening ends early once we collect this much energy per tick.
+    pub build_up_income: u32,
+    /// Our army has to be this many times stronger than the enemy's to start a push.
+    pub start_push_ratio: f64,
+    /// A push goes on until our army is no longer this many times stronger than the enemy's.
+    pub hold_push_ratio: f64,
+    /// We don't push with an army weaker than this, however weak the enemy is.
+    pub min_push_strength: u32,
+    /// A new phase has to be wanted for this many ticks in a row before we switch to it.
+    pub confirm_ticks: u32,
+    /// A phase lasts at least this many ticks before we switch away from it.
+    pub min_phase_ticks: u32,
+}
+
+impl PhaseStrategy {
+    fn validate(&self) -> Result<()> {
+        if self.hold_push_ratio > self.start_push_ratio {
+            bail!("phases.hold_push_ratio must not be greater than start_push_ratio");
+        }
+
+        if self.confirm_ticks == 0 {
+            bail!("phases.confirm_ticks must be greater than 0");
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for PhaseStrategy {
+    fn default() -> Self {
+        Self {
+            opening_ticks: 100,
+            build_up_income: 5,
+            start_push_ratio: 1.5,
+            hold_push_ratio: 1.1,
+            min_push_strength: 100,
+            confirm_ticks: 5,
+            min_phase_ticks: 20,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct TowerStrategy {
+    /// Towers with less energy than this only fire at enemies they can kill or that are close to
+    /// what they defend, and don't heal.
+    pub reserve_energy: u32,
+    /// A volley that does less damage than this, after the enemy's healing, isn't worth the
+    /// energy.
+    pub min_attack_damage: u32,
+}
+
+impl TowerStrategy {
+    fn validate(&self) -> Result<()> {
+        if self.min_attack_damage == 0 {
+            bail!("towers.min_attack_damage must be greater than 0");
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for TowerStrategy {
+    fn default() -> Self {
+        Self {
+            reserve_energy: 30,
+            min_attack_damage: 50,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct RetreatStrategy {
+    /// Creeps expected to drop below this share of their hits next tick fall back.
+    pub retreat_hits_ratio: f64,
+    /// Retreating creeps go back to the fight once healed above this share of their hits.
+    pub reengage_hits_ratio: f64,
+    /// Creeps with less than this share of their attack, ranged attack and heal parts left fall
+    /// back.
+    pub min_combat_parts_ratio: f64,
+    /// How many tiles from home toward the enemy the rally point is.
+    pub rally_distance: u8,
+}
+
+impl RetreatStrategy {
+    fn validate(&self) -> Result<()> {
+        if self.reengage_hits_ratio <= self.retreat_hits_ratio {
+            bail!("retreat.reengage_hits_ratio must be greater than retreat_hits_ratio");
+        }
+
+        if self.reengage_hits_ratio > 1.0 {
+            bail!("retreat.reengage_hits_ratio must not be greater than 1");
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for RetreatStrategy {
+    fn default() -> Self {
+        Self {
+            retreat_hits_ratio: 0.4,
+            reengage_hits_ratio: 0.9,
+            min_combat_parts_ratio: 0.5,
+            rally_distance: 10,
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use eyre::Result;
+use js_sys::{Object, Reflect};
+use log::warn;
+use screeps_arena::{game::utils::get_objects_by_prototype, prototypes, Creep};
+use wasm_bindgen::{JsCast, JsValue};
+
+use super::{
+    influence::{CostMatrix, IMPASSABLE_COST},
+    strategy::get_strategy,
+    traffic::TrafficManager,
+    utilities::get_position,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveStatus {
+    Moving,
+    Arrived,
+    /// Standing still because of fatigue, the creep will move again on its own.
+    Fatigued,
+    /// Standing still without fatigue, something is in the way.
+    Blocked {
+        stalled_ticks: u8,
+    },
+    /// Blocked for longer than the strategy allows, whatever the creep is doing should be
+    /// reassigned.
+    Stuck,
+}
+
+struct MovementRecord {
+    position: (u8, u8),
+    target: (u8, u8),
+    stalled_ticks: u8,
+}
+
+impl MovementRecord {
+    fn new(position: (u8, u8), target: (u8, u8)) -> Self {
+        Self {
+            position,
+            target,
+            stalled_ticks: 0,
+        }
+    }
+
+    /// Compares where the creep is now with last tick. Standing still while tired doesn't count
+    /// as stalling, a new target or a new position starts the count over.
+    fn update(
+        &mut self,
+        position: (u8, u8),
+        target: (u8, u8),
+        is_fatigued: bool,
+        escalation_ticks: u8,
+    ) -> MoveStatus {
+        if self.target != target || self.position != position {
+            *self = Self::new(position, target);
+            return MoveStatus::Moving;
+        }
+
+        if is_fatigued {
+            return MoveStatus::Fatigued;
+        }
+
+        self.stalled_ticks = self.stalled_ticks.saturating_add(1);
+        if self.stalled_ticks >= escalation_ticks {
+            return MoveStatus::Stuck;
+        }
+
+        MoveStatus::Blocked {
+            stalled_ticks: self.stalled_ticks,
+        }
+    }
+}
+
+/// Remembers where every moving creep was last tick to notice creeps that keep calling `move_to`
+/// without getting anywhere.
+#[derive(Default)]
+pub struct StuckDetector {
+    records: HashMap<String, MovementRecord>,
+}
+
+impl StuckDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compares the creep with last tick. Call once per tick for every creep that wants to move
+    /// to the target, before moving it.
+    pub fn check(&mut self, creep: &Creep, target: (u8, u8)) -> MoveStatus {
+        let id = match creep.id().as_string() {
+            Some(id) => id,
+            None => return MoveStatus::Moving,
+        };
+        let position = (creep.x(), creep.y());
+
+        if position == target {
+            self.records.remove(&id);
+            return MoveStatus::Arrived;
+        }
+
+        let record = match self.records.entry(id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                entry.insert(MovementRecord::new(position, target));
+                return MoveStatus::Moving;
+            }
+        };
+
+        let escalation_ticks = get_strategy().movement.stalled_ticks_before_escalation;
+        let status = record.update(position, target, creep.fatigue() > 0, escalation_ticks);
+        if status == MoveStatus::Stuck && record.stalled_ticks == escalation_ticks {
+            warn!(
+                "creep {} is stuck at {:?} on its way to {:?}",
+                creep.id(),
+                position,
+                target
+            );
+        }
+
+        status
+    }
+
+    /// Submits the creep's move toward the target to the traffic manager, pathing around our
+    /// own creeps once it has been blocked for long enough. The returned status tells the caller
+    /// when to give up on the target.
+    pub fn move_to(
+        &mut self,
+        creep: &Creep,
+        target: &Object,
+        options: Option<&Object>,
+        traffic: &mut TrafficManager,
+        priority: u8,
+    ) -> Result<MoveStatus> {
+        let target_position = match get_position(target) {
+            Ok(position) => position,
+            Err(_) => {
+                traffic.request_move_toward(creep, target, options, priority)?;
+                return Ok(MoveStatus::Moving);
+            }
+        };
+
+        let status = self.check(creep, target_position);
+        let repath_ticks = get_strategy().movement.stalled_ticks_before_repath;
+        match status {
+            MoveStatus::Arrived => traffic.hold(creep),
+            MoveStatus::Blocked { stalled_ticks } if stalled_ticks >= repath_ticks => {
+                let avoid_options = avoid_creeps_options(creep, options);
+                traffic.request_move_toward(creep, target, Some(&avoid_options), priority)?;
+            }
+            MoveStatus::Stuck => {
+                let avoid_options = avoid_creeps_options(creep, options);
+                traffic.request_move_toward(creep, target, Some(&avoid_options), priority)?;
+            }
+            MoveStatus::Moving | MoveStatus::Fatigued | MoveStatus::Blocked { .. } => {
+                traffic.request_move_toward(creep, target, options, priority)?;
+            }
+        }
+
+        Ok(status)
+    }
+
+    /// Drops the records of creeps that are no longer alive.
+    pub fn forget_dead(&mut self, my_creeps: &[Creep]) {
+        let alive = my_creeps
+            .iter()
+            .filter_map(|creep| creep.id().as_string())
+            .collect::<Vec<String>>();
+        self.records.retain(|id, _| alive.contains(id));
+    }
+}
+
+/// A copy of the caller's move options that also treats every creep but this one as a wall, so
+/// the path goes around them. Costs from the caller's cost matrix are kept, the caller's options
+/// and matrix are left alone.
+fn avoid_creeps_options(creep: &Creep, options: Option<&Object>) -> Object {
+    let avoid_options = Object::new();
+    let cost_matrix_key = JsValue::from_str("costMatrix");
+    let mut cost_matrix = None;
+    if let Some(options) = options {
+        Object::assign(&avoid_options, options);
+        cost_matrix = Reflect::get(options, &cost_matrix_key)
+            .ok()
+            .filter(|cost_matrix| cost_matrix.is_object())
+            .map(|cost_matrix| cost_matrix.unchecked_into::<CostMatrix>().copy());
+    }
+
+    let cost_matrix = cost_matrix.unwrap_or_else(CostMatrix::new);
+    for other in get_objects_by_prototype(prototypes::CREEP) {
+        if other.id() != creep.id() {
+            cost_matrix.set(other.x(), other.y(), IMPASSABLE_COST);
+        }
+    }
+
+    // setting a property on a plain object we just created cannot fail
+    Reflect::set(&avoid_options, &cost_matrix_key, &cost_matrix).ok();
+    avoid_options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ESCALATION_TICKS: u8 = 3;
+
+    #[test]
+    fn moving_creeps_are_never_stalled() {
+        let mut record = MovementRecord::new((5, 5), (10, 10));
+
+        assert_eq!(
+            record.update((6, 6), (10, 10), false, ESCALATION_TICKS),
+            MoveStatus::Moving
+        );
+        assert_eq!(record.stalled_ticks, 0);
+    }
+
+    #[test]
+    fn standing_still_counts_up_to_stuck() {
+        let mut record = MovementRecord::new((5, 5), (10, 10));
+
+        assert_eq!(
+            record.update((5, 5), (10, 10), false, ESCALATION_TICKS),
+            MoveStatus::Blocked { stalled_ticks: 1 }
+        );
+        assert_eq!(
+            record.update((5, 5), (10, 10), false, ESCALATION_TICKS),
+            MoveStatus::Blocked { stalled_ticks: 2 }
+        );
+        assert_eq!(
+            record.update((5, 5), (10, 10), false, ESCALATION_TICKS),
+            MoveStatus::Stuck
+        );
+        assert_eq!(
+            record.update((5, 5), (10, 10), false, ESCALATION_TICKS),
+            MoveStatus::Stuck
+        );
+    }
+
+    #[test]
+    fn fatigue_doesnt_count_as_stalling() {
+        let mut record = MovementRecord::new((5, 5), (10, 10));
+
+        assert_eq!(
+            record.update((5, 5), (10, 10), true, ESCALATION_TICKS),
+            MoveStatus::Fatigued
+        );
+        assert_eq!(record.stalled_ticks, 0);
+    }
+
+    #[test]
+    fn a_new_target_starts_over() {
+        let mut record = MovementRecord::new((5, 5), (10, 10));
+        record.update((5, 5), (10, 10), false, ESCALATION_TICKS);
+        record.update((5, 5), (10, 10), false, ESCALATION_TICKS);
+
+        assert_eq!(
+            record.update((5, 5), (20, 20), false, ESCALATION_TICKS),
+            MoveStatus::Moving
+        );
+        assert_eq!(
+            record.update((5, 5), (20, 20), false, ESCALATION_TICKS),
+            MoveStatus::Blocked { stalled_ticks: 1 }
+        );
+    }
+}
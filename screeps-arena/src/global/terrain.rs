@@ -0,0 +1,53 @@
+use js_sys::Object;
+use wasm_bindgen::prelude::*;
+
+use super::utilities::create_position_object;
+
+/// Every arena map is 100 by 100 tiles.
+pub const MAP_SIZE: u8 = 100;
+
+#[wasm_bindgen(module = "game/utils")]
+extern "C" {
+    #[wasm_bindgen(js_name = getTerrainAt)]
+    fn get_terrain_at(position: &Object) -> u8;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Terrain {
+    Plain,
+    Swamp,
+    Wall,
+}
+
+impl Terrain {
+    /// Fatigue each part that isn't a move part adds when stepping onto this terrain. Walls can't
+    /// be walked onto.
+    pub fn fatigue_per_part(self) -> Option<u32> {
+        match self {
+            Terrain::Plain => Some(2),
+            Terrain::Swamp => Some(10),
+            Terrain::Wall => None,
+        }
+    }
+}
+
+/// The terrain at the position. Positions off the map are treated as walls.
+pub fn get_terrain(x: u8, y: u8) -> Terrain {
+    if x >= MAP_SIZE || y >= MAP_SIZE {
+        return Terrain::Wall;
+    }
+
+    match get_terrain_at(&create_position_object(x, y)) {
+        1 => Terrain::Wall,
+        2 => Terrain::Swamp,
+        _ => Terrain::Plain,
+    }
+}
+
+/// Chebyshev distance, which is the number of steps between two tiles when diagonal moves are
+/// allowed.
+pub fn get_range(from: (u8, u8), to: (u8, u8)) -> u8 {
+    let dx = (from.0 as i16 - to.0 as i16).abs();
+    let dy = (from.1 as i16 - to.1 as i16).abs();
+    dx.max(dy) as u8
+}
@@ -0,0 +1,180 @@
+use screeps_arena::{Creep, ResourceType, StructureTower};
+
+use super::{
+    classifier::{classify, CombatRole, HEAL_POWER, RANGED_HEAL_POWER},
+    influence::{tower_power_at_range, TOWER_ENERGY_COST, TOWER_POWER_ATTACK, TOWER_POWER_HEAL},
+    strategy::get_strategy,
+    terrain::get_range,
+};
+
+/// Healers undo the damage we do to everyone around them, so they go first.
+const HEALER_PRIORITY: f64 = 2.0;
+/// Enemies about to reach what we defend go before anything else that isn't a kill.
+const DANGER_PRIORITY: f64 = 3.0;
+/// A shot that kills is always worth more than one that doesn't.
+const KILL_PRIORITY: f64 = 5.0;
+
+struct Target<'a> {
+    enemy: &'a Creep,
+    /// Healing the enemy gets each tick from itself and its neighbors.
+    healing: u32,
+    can_kill: bool,
+    is_dangerous: bool,
+    score: f64,
+}
+
+/// Fires our towers. Enemies are scored by how much threat a volley removes, healers and enemies
+/// close to what the towers defend first, and a kill beats everything. Towers share a target
+/// until it dies, the rest move on to the next one or heal our creeps when no shot is worth the
+/// energy. Towers low on energy keep it for kills and enemies close to what they defend.
+pub struct TowerController {
+    defended: (u8, u8),
+    danger_range: u8,
+}
+
+impl TowerController {
+    /// Enemies within `danger_range` of `defended` are shot at whatever it costs.
+    pub fn new(defended: (u8, u8), danger_range: u8) -> Self {
+        Self {
+            defended,
+            danger_range,
+        }
+    }
+
+    /// Call once per tick.
+    pub fn run(&self, towers: &[StructureTower], my_creeps: &[Creep], enemies: &[Creep]) {
+        let mut ready_towers = towers
+            .iter()
+            .filter(|tower| get_energy(tower) >= TOWER_ENERGY_COST)
+            .collect::<Vec<&StructureTower>>();
+        if ready_towers.is_empty() {
+            return;
+        }
+
+        let mut targets = enemies
+            .iter()
+            .map(|enemy| self.score_target(enemy, enemies, &ready_towers))
+            .filter(|target| self.is_worth_firing(target, &ready_towers))
+            .collect::<Vec<Target>>();
+        targets.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        for target in targets {
+            if ready_towers.is_empty() {
+                return;
+            }
+
+            // the closest towers do the most damage, so they fire first
+            ready_towers.sort_by_key(|tower| tower.get_range_to(target.enemy));
+            let needed_damage = target.enemy.hits() + target.healing;
+            let mut damage = 0;
+            while damage < needed_damage && !ready_towers.is_empty() {
+                let tower = ready_towers.remove(0);
+                damage +=
+                    tower_power_at_range(TOWER_POWER_ATTACK, tower.get_range_to(target.enemy));
+                tower.attack(target.enemy);
+            }
+        }
+
+        self.heal(&ready_towers, my_creeps);
+    }
+
+    fn score_target<'a>(
+        &self,
+        enemy: &'a Creep,
+        enemies: &[Creep],
+        towers: &[&StructureTower],
+    ) -> Target<'a> {
+        let classification = classify(enemy);
+        let damage = towers
+            .iter()
+            .map(|tower| tower_power_at_range(TOWER_POWER_ATTACK, tower.get_range_to(enemy)))
+            .sum::<u32>();
+        let healing = get_healing(enemy, enemies);
+        let net_damage = damage.saturating_sub(healing);
+        let can_kill = net_damage >= enemy.hits();
+        let is_dangerous = get_range((enemy.x(), enemy.y()), self.defended) <= self.danger_range;
+
+        let mut score = net_damage.min(enemy.hits()) as f64 * classification.threat.kill_priority();
+        if classification.primary == CombatRole::Healer {
+            score *= HEALER_PRIORITY;
+        }
+        if is_dangerous {
+            score *= DANGER_PRIORITY;
+        }
+        if can_kill {
+            score *= KILL_PRIORITY;
+        }
+
+        Target {
+            enemy,
+            healing,
+            can_kill,
+            is_dangerous,
+            score,
+        }
+    }
+
+    fn is_worth_firing(&self, target: &Target, towers: &[&StructureTower]) -> bool {
+        if target.can_kill || target.is_dangerous {
+            return true;
+        }
+
+        let strategy = &get_strategy().towers;
+        let energy = towers.iter().map(|tower| get_energy(tower)).sum::<u32>();
+        let is_low_on_energy = energy < strategy.reserve_energy * towers.len() as u32;
+        let damage = towers
+            .iter()
+            .map(|tower| tower_power_at_range(TOWER_POWER_ATTACK, tower.get_range_to(target.enemy)))
+            .sum::<u32>();
+
+        !is_low_on_energy && damage.saturating_sub(target.healing) >= strategy.min_attack_damage
+    }
+
+    /// Heals the most damaged of our creeps, as long as the towers have energy to spare. Every
+    /// tower heals whoever is missing the most hits after the heals of the towers before it.
+    fn heal(&self, towers: &[&StructureTower], my_creeps: &[Creep]) {
+        let reserve_energy = get_strategy().towers.reserve_energy;
+        let mut missing_hits = my_creeps
+            .iter()
+            .map(|creep| (creep, creep.hits_max() - creep.hits()))
+            .collect::<Vec<(&Creep, u32)>>();
+
+        for tower in towers {
+            if get_energy(tower) < reserve_energy {
+                continue;
+            }
+
+            let most_hurt = missing_hits
+                .iter_mut()
+                .filter(|(_, missing)| *missing > 0)
+                .max_by_key(|(_, missing)| *missing);
+            match most_hurt {
+                Some((creep, missing)) => {
+                    tower.heal(*creep);
+                    let healed = tower_power_at_range(TOWER_POWER_HEAL, tower.get_range_to(*creep));
+                    *missing = missing.saturating_sub(healed);
+                }
+                None => return,
+            }
+        }
+    }
+}
+
+/// Healing the enemy can get from itself and the healers around it in a tick.
+fn get_healing(enemy: &Creep, enemies: &[Creep]) -> u32 {
+    enemies
+        .iter()
+        .map(|healer| {
+            let heals = classify(healer).parts.heals;
+            match healer.get_range_to(enemy) {
+                0..=1 => heals * HEAL_POWER,
+                2..=3 => heals * RANGED_HEAL_POWER,
+                _ => 0,
+            }
+        })
+        .sum()
+}
+
+fn get_energy(tower: &StructureTower) -> u32 {
+    tower.store().get_used_capacity(Some(ResourceType::Energy))
+}
@@ -0,0 +1,236 @@
+use std::collections::{HashMap, VecDeque};
+
+use screeps_arena::Creep;
+
+use super::{
+    body::CreepStats,
+    terrain::{get_range, get_terrain},
+};
+
+/// How many ticks of positions and hits are kept for every enemy.
+const HISTORY_LENGTH: usize = 10;
+/// Enemies that haven't been seen for this many ticks are assumed dead and forgotten.
+const FORGET_AFTER_TICKS: u32 = 5;
+/// Interceptions are never planned further ahead than this, longer predictions are mostly guesses.
+const MAX_INTERCEPT_TICKS: u32 = 5;
+
+/// Everything we have seen of a single enemy creep.
+#[derive(Clone, Debug)]
+pub struct EnemyRecord {
+    pub id: String,
+    pub first_seen: u32,
+    pub last_seen: u32,
+    /// Oldest first, `(tick, x, y)`.
+    pub positions: VecDeque<(u32, u8, u8)>,
+    /// Oldest first, `(tick, hits)`.
+    pub hits: VecDeque<(u32, u32)>,
+    pub stats: CreepStats,
+}
+
+impl EnemyRecord {
+    fn new(id: String, tick: u32, creep: &Creep) -> Self {
+        let mut record = Self {
+            id,
+            first_seen: tick,
+            last_seen: tick,
+            positions: VecDeque::with_capacity(HISTORY_LENGTH),
+            hits: VecDeque::with_capacity(HISTORY_LENGTH),
+            stats: CreepStats::default(),
+        };
+        record.observe(tick, creep);
+        record
+    }
+
+    fn observe(&mut self, tick: u32, creep: &Creep) {
+        self.last_seen = tick;
+        self.stats = CreepStats::from_creep(creep);
+        push_limited(&mut self.positions, (tick, creep.x(), creep.y()));
+        push_limited(&mut self.hits, (tick, creep.hits()));
+    }
+
+    pub fn position(&self) -> (u8, u8) {
+        self.positions.back().map_or((0, 0), |(_, x, y)| (*x, *y))
+    }
+
+    /// Average tiles moved per tick on each axis over the kept history.
+    pub fn velocity(&self) -> (f64, f64) {
+        match (self.positions.front(), self.positions.back()) {
+            (Some((first_tick, first_x, first_y)), Some((last_tick, last_x, last_y)))
+                if last_tick > first_tick =>
+            {
+                let ticks = (last_tick - first_tick) as f64;
+                (
+                    (*last_x as f64 - *first_x as f64) / ticks,
+                    (*last_y as f64 - *first_y as f64) / ticks,
+                )
+            }
+            _ => (0.0, 0.0),
+        }
+    }
+
+    /// The direction the enemy is moving in as a step of -1, 0 or 1 on each axis.
+    pub fn heading(&self) -> (i8, i8) {
+        let (velocity_x, velocity_y) = self.velocity();
+        (to_step(velocity_x), to_step(velocity_y))
+    }
+
+    /// Where the enemy will be in `ticks` ticks if it keeps its heading, slowed down by the
+    /// fatigue it has now and the fatigue of the terrain it walks over. The prediction stops in
+    /// front of walls.
+    pub fn predict_position(&self, ticks: u32) -> (u8, u8) {
+        let (step_x, step_y) = self.heading();
+        let mut position = self.position();
+        if step_x == 0 && step_y == 0 {
+            return position;
+        }
+
+        let mut remaining_ticks = match self.stats.ticks_until_move() {
+            Some(wait_ticks) if wait_ticks <= ticks => ticks - wait_ticks,
+            _ => return position,
+        };
+        loop {
+            let next_x = position.0 as i16 + step_x as i16;
+            let next_y = position.1 as i16 + step_y as i16;
+            if next_x < 0 || next_y < 0 {
+                break;
+            }
+
+            let next = (next_x as u8, next_y as u8);
+            let step_ticks = match self.stats.ticks_per_step(get_terrain(next.0, next.1)) {
+                Some(step_ticks) => step_ticks,
+                None => break,
+            };
+            if step_ticks > remaining_ticks {
+                break;
+            }
+
+            remaining_ticks -= step_ticks;
+            position = next;
+        }
+
+        position
+    }
+
+    /// True when the enemy got closer to the target over the kept history and is still heading
+    /// towards it.
+    pub fn is_advancing_toward(&self, target: (u8, u8)) -> bool {
+        let oldest = match self.positions.front() {
+            Some((_, x, y)) => (*x, *y),
+            None => return false,
+        };
+        let current = self.position();
+        let (step_x, step_y) = self.heading();
+        let next = (
+            (current.0 as i16 + step_x as i16).max(0) as u8,
+            (current.1 as i16 + step_y as i16).max(0) as u8,
+        );
+
+        get_range(current, target) < get_range(oldest, target)
+            && get_range(next, target) < get_range(current, target)
+    }
+
+    /// Hits lost per tick over the kept history, negative when the enemy is being healed faster
+    /// than we damage it.
+    pub fn damage_per_tick(&self) -> f64 {
+        match (self.hits.front(), self.hits.back()) {
+            (Some((first_tick, first_hits)), Some((last_tick, last_hits)))
+                if last_tick > first_tick =>
+            {
+                (*first_hits as f64 - *last_hits as f64) / (last_tick - first_tick) as f64
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// Keeps a record of every enemy across ticks, so behaviour can depend on where enemies are going
+/// and not only where they are.
+#[derive(Default)]
+pub struct EnemyTracker {
+    records: HashMap<String, EnemyRecord>,
+}
+
+impl EnemyTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call once per tick with every visible enemy.
+    pub fn update(&mut self, tick: u32, enemies: &[Creep]) {
+        for enemy in enemies {
+            let id = match enemy.id().as_string() {
+                Some(id) => id,
+                None => continue,
+            };
+
+            match self.records.get_mut(&id) {
+                Some(record) => record.observe(tick, enemy),
+                None => {
+                    let record = EnemyRecord::new(id.clone(), tick, enemy);
+                    self.records.insert(id, record);
+                }
+            }
+        }
+
+        self.records
+            .retain(|_, record| tick - record.last_seen <= FORGET_AFTER_TICKS);
+    }
+
+    pub fn get(&self, id: &str) -> Option<&EnemyRecord> {
+        self.records.get(id)
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = &EnemyRecord> {
+        self.records.values()
+    }
+
+    pub fn advancing_toward(&self, target: (u8, u8)) -> Vec<&EnemyRecord> {
+        self.records
+            .values()
+            .filter(|record| record.is_advancing_toward(target))
+            .collect()
+    }
+
+    /// The closest any enemy advancing toward the target is, or will be in `ticks` ticks.
+    pub fn closest_approach(&self, target: (u8, u8), ticks: u32) -> Option<u8> {
+        self.advancing_toward(target)
+            .iter()
+            .map(|record| {
+                get_range(record.position(), target)
+                    .min(get_range(record.predict_position(ticks), target))
+            })
+            .min()
+    }
+
+    /// Where to move to meet the enemy when coming from `from`: its predicted position after
+    /// about as many ticks as it takes us to get there. Enemies we haven't tracked yet are met
+    /// where they stand.
+    pub fn intercept_point(&self, enemy: &Creep, from: (u8, u8)) -> (u8, u8) {
+        let position = (enemy.x(), enemy.y());
+        match enemy.id().as_string().and_then(|id| self.records.get(&id)) {
+            Some(record) => {
+                let ticks = (get_range(from, position) as u32).min(MAX_INTERCEPT_TICKS);
+                record.predict_position(ticks)
+            }
+            None => position,
+        }
+    }
+}
+
+fn push_limited<T>(history: &mut VecDeque<T>, value: T) {
+    if history.len() == HISTORY_LENGTH {
+        history.pop_front();
+    }
+    history.push_back(value);
+}
+
+/// Anything slower than a tile every few ticks is treated as standing still.
+fn to_step(velocity: f64) -> i8 {
+    if velocity > 0.2 {
+        1
+    } else if velocity < -0.2 {
+        -1
+    } else {
+        0
+    }
+}
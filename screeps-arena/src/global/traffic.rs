@@ -0,0 +1,338 @@
+use std::collections::{HashMap, HashSet};
+
+use eyre::{bail, Result};
+use js_sys::Object;
+use screeps_arena::Creep;
+use wasm_bindgen::{prelude::*, JsCast};
+
+use super::{
+    terrain::{get_range, get_terrain, Terrain},
+    utilities::get_position,
+};
+
+#[wasm_bindgen]
+extern "C" {
+    /// Anything with the `move` method of a creep.
+    type Walker;
+
+    /// Steps onto the neighboring tile in the direction without pathfinding, so the creep steps
+    /// even when another of our creeps is standing there and moves away this tick.
+    #[wasm_bindgen(method, js_name = move)]
+    fn step(this: &Walker, direction: u8) -> i8;
+}
+
+/// Creeps falling back to safety get through first.
+pub const RETREAT_PRIORITY: u8 = 4;
+pub const COMBAT_PRIORITY: u8 = 3;
+pub const HEALER_PRIORITY: u8 = 2;
+pub const WORKER_PRIORITY: u8 = 1;
+
+const NEIGHBOR_OFFSETS: [(i16, i16); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+enum Intent {
+    /// Wants to step onto the adjacent tile.
+    Move { to: (u8, u8), priority: u8 },
+    /// Is working where it stands and must not be pushed away.
+    Hold,
+    /// Has nothing to do and can be shoved aside to make room.
+    Idle,
+}
+
+struct Entry {
+    creep: Creep,
+    from: (u8, u8),
+    intent: Intent,
+}
+
+/// Coordinates the movement of our creeps for a tick. Creeps submit the step they want to take
+/// instead of moving themselves, then `resolve` gives each creep a single move so that higher
+/// priority creeps go first, creeps that want each other's tile swap, and idle creeps are shoved
+/// out of the way instead of blocking a chokepoint.
+#[derive(Default)]
+pub struct TrafficManager {
+    entries: Vec<Entry>,
+}
+
+impl TrafficManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks to step onto `to`, which has to be next to the creep. A creep that submits more than
+    /// once keeps its highest priority move, the latest one when the priorities are the same.
+    pub fn request_move(&mut self, creep: &Creep, to: (u8, u8), priority: u8) -> Result<()> {
+        if get_range((creep.x(), creep.y()), to) != 1 {
+            bail!(
+                "can only move to a neighboring tile, {:?} is not next to {:?}",
+                to,
+                (creep.x(), creep.y())
+            );
+        }
+        let intent = Intent::Move { to, priority };
+
+        match self.find_entry(creep) {
+            Some(entry) => match entry.intent {
+                Intent::Move {
+                    priority: current_priority,
+                    ..
+                } if current_priority > priority => (),
+                _ => entry.intent = intent,
+            },
+            None => self.add_entry(creep, intent),
+        }
+
+        Ok(())
+    }
+
+    /// Asks to take the first step of the path to the target. Creeps already at the target
+    /// hold their position.
+    pub fn request_move_toward(
+        &mut self,
+        creep: &Creep,
+        target: &Object,
+        options: Option<&Object>,
+        priority: u8,
+    ) -> Result<()> {
+        let path = creep.find_path_to(target, options);
+        match path.iter().next() {
+            Some(step) => self.request_move(creep, get_position(&Object::from(step))?, priority),
+            None => {
+                self.hold(creep);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn hold(&mut self, creep: &Creep) {
+        if self.find_entry(creep).is_none() {
+            self.add_entry(creep, Intent::Hold);
+        }
+    }
+
+    pub fn idle(&mut self, creep: &Creep) {
+        if self.find_entry(creep).is_none() {
+            self.add_entry(creep, Intent::Idle);
+        }
+    }
+
+    /// Works out every move and issues them. Call once per tick after every creep has submitted.
+    pub fn resolve(self) {
+        let mut resolver = Resolver::new(&self.entries);
+
+        let mut movers = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| match entry.intent {
+                Intent::Move { priority, .. } => Some((index, priority)),
+                _ => None,
+            })
+            .collect::<Vec<(usize, u8)>>();
+        movers.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        for (index, _) in movers {
+            resolver.try_move(index, &mut HashSet::new());
+        }
+
+        for (entry, step) in self.entries.iter().zip(resolver.steps) {
+            if let Some(direction) = step.and_then(|to| get_direction(entry.from, to)) {
+                let creep: &JsValue = &entry.creep;
+                creep.unchecked_ref::<Walker>().step(direction);
+            }
+        }
+    }
+
+    fn find_entry(&mut self, creep: &Creep) -> Option<&mut Entry> {
+        let id = creep.id();
+        self.entries.iter_mut().find(|entry| entry.creep.id() == id)
+    }
+
+    fn add_entry(&mut self, creep: &Creep, intent: Intent) {
+        self.entries.push(Entry {
+            creep: creep.clone(),
+            from: (creep.x(), creep.y()),
+            intent,
+        });
+    }
+}
+
+struct Resolver<'a> {
+    entries: &'a [Entry],
+    occupants: HashMap<(u8, u8), usize>,
+    /// The tile every decided creep will end the tick on.
+    claimed: HashMap<(u8, u8), usize>,
+    decided: Vec<bool>,
+    steps: Vec<Option<(u8, u8)>>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(entries: &'a [Entry]) -> Self {
+        Self {
+            entries,
+            occupants: entries
+                .iter()
+                .enumerate()
+                .map(|(index, entry)| (entry.from, index))
+                .collect(),
+            claimed: HashMap::new(),
+            decided: vec![false; entries.len()],
+            steps: vec![None; entries.len()],
+        }
+    }
+
+    /// Decides whether the creep can take its step, moving the creeps in the way first.
+    /// `visiting` holds the creeps waiting on this one, to stop chains that loop back on
+    /// themselves.
+    fn try_move(&mut self, index: usize, visiting: &mut HashSet<usize>) -> bool {
+        if self.decided[index] {
+            return self.steps[index].is_some();
+        }
+
+        let from = self.entries[index].from;
+        let to = match self.entries[index].intent {
+            Intent::Move { to, .. } => to,
+            _ => return false,
+        };
+
+        if !is_walkable(to) || self.claimed.contains_key(&to) {
+            self.stay(index);
+            return false;
+        }
+
+        visiting.insert(index);
+        let can_move = match self.occupants.get(&to).copied() {
+            None => true,
+            Some(other) if self.decided[other] => self.steps[other].is_some(),
+            Some(other) => match self.entries[other].intent {
+                Intent::Move { to: other_to, .. }
+                    if other_to == from && !self.claimed.contains_key(&from) =>
+                {
+                    self.step(other, from);
+                    true
+                }
+                Intent::Move { .. } => !visiting.contains(&other) && self.try_move(other, visiting),
+                Intent::Hold => false,
+                Intent::Idle => self.shove(other, from),
+            },
+        };
+        visiting.remove(&index);
+
+        if can_move {
+            self.step(index, to);
+        } else {
+            self.stay(index);
+        }
+
+        can_move
+    }
+
+    /// Moves an idle creep to any free neighboring tile, or into the tile the creep pushing it
+    /// is leaving when there is none. When another creep already claimed that tile too the idle
+    /// creep stays and blocks the pusher.
+    fn shove(&mut self, index: usize, pusher_from: (u8, u8)) -> bool {
+        let from = self.entries[index].from;
+        let free_tile = NEIGHBOR_OFFSETS
+            .iter()
+            .filter_map(|(dx, dy)| offset(from, *dx, *dy))
+            .find(|tile| {
+                is_walkable(*tile)
+                    && !self.occupants.contains_key(tile)
+                    && !self.claimed.contains_key(tile)
+            });
+
+        match free_tile {
+            Some(tile) => self.step(index, tile),
+            None if !self.claimed.contains_key(&pusher_from) => self.step(index, pusher_from),
+            None => {
+                self.stay(index);
+                return false;
+            }
+        }
+        true
+    }
+
+    fn step(&mut self, index: usize, to: (u8, u8)) {
+        self.decided[index] = true;
+        self.steps[index] = Some(to);
+        self.claimed.insert(to, index);
+    }
+
+    fn stay(&mut self, index: usize) {
+        self.decided[index] = true;
+        self.steps[index] = None;
+        self.claimed.insert(self.entries[index].from, index);
+    }
+}
+
+/// The game's direction constant for a step to a neighboring tile, counting clockwise from 1 for
+/// up to 8 for up left. None when the tiles aren't neighbors.
+fn get_direction(from: (u8, u8), to: (u8, u8)) -> Option<u8> {
+    let dx = to.0 as i16 - from.0 as i16;
+    let dy = to.1 as i16 - from.1 as i16;
+
+    match (dx, dy) {
+        (0, -1) => Some(1),
+        (1, -1) => Some(2),
+        (1, 0) => Some(3),
+        (1, 1) => Some(4),
+        (0, 1) => Some(5),
+        (-1, 1) => Some(6),
+        (-1, 0) => Some(7),
+        (-1, -1) => Some(8),
+        _ => None,
+    }
+}
+
+fn offset(position: (u8, u8), dx: i16, dy: i16) -> Option<(u8, u8)> {
+    let x = position.0 as i16 + dx;
+    let y = position.1 as i16 + dy;
+    if x < 0 || y < 0 {
+        return None;
+    }
+
+    Some((x as u8, y as u8))
+}
+
+fn is_walkable(position: (u8, u8)) -> bool {
+    get_terrain(position.0, position.1) != Terrain::Wall
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directions_go_clockwise_from_up() {
+        let from = (10, 10);
+        let neighbors = [
+            (10, 9),
+            (11, 9),
+            (11, 10),
+            (11, 11),
+            (10, 11),
+            (9, 11),
+            (9, 10),
+            (9, 9),
+        ];
+
+        for (index, to) in neighbors.iter().enumerate() {
+            assert_eq!(get_direction(from, *to), Some(index as u8 + 1));
+        }
+    }
+
+    #[test]
+    fn only_neighbors_have_a_direction() {
+        assert_eq!(get_direction((10, 10), (10, 10)), None);
+        assert_eq!(get_direction((10, 10), (12, 10)), None);
+        assert_eq!(get_direction((0, 0), (2, 2)), None);
+    }
+}
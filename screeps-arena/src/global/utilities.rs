@@ -30,42 +30,37 @@ pub fn creep_to_array(creeps: &[Creep]) -> Array {
 }
 
 #[allow(dead_code)]
-pub fn object_to_creep(object: &Object) -> Option<Creep> {
-    let object_id = match Reflect::get(object, &JsValue::from_str("id")) {
-        Ok(id) => JsString::from(id),
-        Err(_) => {
-            warn!("Error getting id from object");
-            panic!();
-        }
-    };
+pub fn object_to_creep(object: &Object) -> Result<Option<Creep>> {
+    let object_id = get_object_id(object)?;
 
-    get_objects_by_prototype(prototypes::CREEP)
+    Ok(get_objects_by_prototype(prototypes::CREEP)
         .into_iter()
-        .find(|creep| creep.id() == object_id)
+        .find(|creep| creep.id() == object_id))
 }
 
 #[allow(dead_code)]
-pub fn object_to_container(object: &Object) -> Option<StructureContainer> {
-    let object_id = match Reflect::get(object, &JsValue::from_str("id")) {
-        Ok(id) => JsString::from(id),
-        Err(_) => {
-            warn!("Error getting id from object");
-            panic!();
-        }
-    };
+pub fn object_to_container(object: &Object) -> Result<Option<StructureContainer>> {
+    let object_id = get_object_id(object)?;
 
-    get_objects_by_prototype(prototypes::STRUCTURE_CONTAINER)
+    Ok(get_objects_by_prototype(prototypes::STRUCTURE_CONTAINER)
         .into_iter()
-        .find(|structure| structure.id() == object_id)
+        .find(|structure| structure.id() == object_id))
+}
+
+fn get_object_id(object: &Object) -> Result<JsString> {
+    match Reflect::get(object, &JsValue::from_str("id")) {
+        Ok(id) => Ok(JsString::from(id)),
+        Err(_) => eyre::bail!("Error getting id from object"),
+    }
 }
 
 #[allow(dead_code)]
-pub fn get_closest_creep(creep: &Creep, other_creeps: &Vec<Creep>) -> Option<Creep> {
+pub fn get_closest_creep(creep: &Creep, other_creeps: &Vec<Creep>) -> Result<Option<Creep>> {
     let other_creeps_array = creep_to_array(other_creeps);
     if let Some(closest_creep_object) = creep.find_closest_by_path(&other_creeps_array, None) {
         object_to_creep(&closest_creep_object)
     } else {
-        None
+        Ok(None)
     }
 }
 
@@ -82,21 +77,40 @@ pub fn containers_to_array(containers: &Vec<StructureContainer>) -> Array {
 
 pub fn create_position_object(x: u8, y: u8) -> Object {
     let position = Object::new();
+    // setting a property on a plain object we just created cannot fail
     Reflect::set(
         &position,
         &JsValue::from_str("x"),
         &JsValue::from_str(x.to_string().as_str()),
     )
-    .unwrap();
+    .ok();
     Reflect::set(
         &position,
         &JsValue::from_str("y"),
         &JsValue::from_str(y.to_string().as_str()),
     )
-    .unwrap();
+    .ok();
     position
 }
 
+/// Reads the position of anything with an `x` and `y`, like a game object or a path step.
+pub fn get_position(object: &Object) -> Result<(u8, u8)> {
+    let get_coordinate = |key: &str| {
+        Reflect::get(object, &JsValue::from_str(key))
+            .ok()
+            .and_then(|value| {
+                value
+                    .as_f64()
+                    .or_else(|| value.as_string().and_then(|value| value.parse().ok()))
+            })
+    };
+
+    match (get_coordinate("x"), get_coordinate("y")) {
+        (Some(x), Some(y)) => Ok((x as u8, y as u8)),
+        _ => eyre::bail!("object has no position"),
+    }
+}
+
 pub fn get_spawn(my: bool) -> Option<StructureSpawn> {
     let spawns = get_objects_by_prototype(prototypes::STRUCTURE_SPAWN)
         .into_iter()
@@ -122,6 +136,13 @@ pub fn get_containers(_with_energy: bool) -> Vec<StructureContainer> {
         .collect()
 }
 
+/// Logs the error a single creep ran into so that the rest of the creeps can still act this tick.
+pub fn log_creep_error(creep: &Creep, result: Result<()>) {
+    if let Err(error) = result {
+        warn!("Error running creep {}: {:?}", creep.id(), error);
+    }
+}
+
 pub fn get_creep_id(creep: &Creep) -> Result<f64> {
     let id = creep.id();
     id.as_f64().ok_or(eyre::eyre!("creep id is not a string"))
@@ -0,0 +1,129 @@
+use js_sys::{Array, Object, Reflect};
+use screeps_arena::Creep;
+use wasm_bindgen::{prelude::*, JsValue};
+
+use super::utilities::create_position_object;
+
+const TARGET_LINE_COLOR: &str = "#ff0000";
+const PATH_COLOR: &str = "#ffffff";
+const STAGING_AREA_COLOR: &str = "#00ff00";
+const THREAT_RANGE_COLOR: &str = "#ff8800";
+const LABEL_COLOR: &str = "#ffff00";
+
+#[wasm_bindgen(module = "game/visual")]
+extern "C" {
+    type Visual;
+
+    #[wasm_bindgen(constructor)]
+    fn new(layer: u8, persistent: bool) -> Visual;
+
+    #[wasm_bindgen(method)]
+    fn line(this: &Visual, from: &Object, to: &Object, style: &Object) -> Visual;
+
+    #[wasm_bindgen(method)]
+    fn circle(this: &Visual, position: &Object, style: &Object) -> Visual;
+
+    #[wasm_bindgen(method)]
+    fn poly(this: &Visual, points: &Array, style: &Object) -> Visual;
+
+    #[wasm_bindgen(method)]
+    fn text(this: &Visual, text: &str, position: &Object, style: &Object) -> Visual;
+}
+
+thread_local! {
+    // A non persistent visual is cleared by the game at the end of every tick, so one layer can be
+    // reused for the whole match.
+    static VISUAL: Visual = Visual::new(10, false);
+}
+
+pub fn draw_target_line(from: &Creep, to_x: u8, to_y: u8) {
+    let style = create_style(&[
+        ("color", JsValue::from_str(TARGET_LINE_COLOR)),
+        ("width", JsValue::from_f64(0.1)),
+        ("opacity", JsValue::from_f64(0.6)),
+    ]);
+
+    VISUAL.with(|visual| {
+        visual.line(
+            &create_position_object(from.x(), from.y()),
+            &create_position_object(to_x, to_y),
+            &style,
+        );
+    });
+}
+
+/// Draws the path the pathfinder would currently take from the creep to the target. This runs a
+/// second path search so only use it while debugging.
+pub fn draw_planned_path(creep: &Creep, target: &Object) {
+    let path = creep.find_path_to(target, None);
+    let points = Array::new();
+    points.push(&create_position_object(creep.x(), creep.y()));
+    for step in path.iter() {
+        points.push(&step);
+    }
+
+    if points.length() < 2 {
+        return;
+    }
+
+    let style = create_style(&[
+        ("stroke", JsValue::from_str(PATH_COLOR)),
+        ("strokeWidth", JsValue::from_f64(0.1)),
+        ("opacity", JsValue::from_f64(0.3)),
+        ("lineStyle", JsValue::from_str("dashed")),
+    ]);
+
+    VISUAL.with(|visual| {
+        visual.poly(&points, &style);
+    });
+}
+
+pub fn draw_staging_area(position: &Object) {
+    let style = create_style(&[
+        ("radius", JsValue::from_f64(1.0)),
+        ("fill", JsValue::from_str(STAGING_AREA_COLOR)),
+        ("opacity", JsValue::from_f64(0.2)),
+        ("stroke", JsValue::from_str(STAGING_AREA_COLOR)),
+    ]);
+
+    VISUAL.with(|visual| {
+        visual.circle(position, &style);
+    });
+}
+
+pub fn draw_threat_range(x: u8, y: u8, range: u8) {
+    let style = create_style(&[
+        ("radius", JsValue::from_f64(range as f64)),
+        ("fill", JsValue::from_str(THREAT_RANGE_COLOR)),
+        ("opacity", JsValue::from_f64(0.1)),
+        ("stroke", JsValue::from_str(THREAT_RANGE_COLOR)),
+    ]);
+
+    VISUAL.with(|visual| {
+        visual.circle(&create_position_object(x, y), &style);
+    });
+}
+
+pub fn draw_role_label(creep: &Creep, label: &str) {
+    draw_text(label, creep.x(), creep.y().saturating_sub(1));
+}
+
+pub fn draw_text(text: &str, x: u8, y: u8) {
+    let style = create_style(&[
+        ("color", JsValue::from_str(LABEL_COLOR)),
+        ("font", JsValue::from_f64(0.5)),
+    ]);
+
+    VISUAL.with(|visual| {
+        visual.text(text, &create_position_object(x, y), &style);
+    });
+}
+
+fn create_style(properties: &[(&str, JsValue)]) -> Object {
+    let style = Object::new();
+    for (key, value) in properties {
+        // setting a property on a plain object we just created cannot fail
+        Reflect::set(&style, &JsValue::from_str(key), value).ok();
+    }
+    style
+}
@@ -3,9 +3,12 @@ use std::{
     rc::Rc,
 };
 
+use log::warn;
 use screeps_arena::Part;
 
-use super::state::State;
+use crate::global::{role::attach_role, stats};
+
+use super::{role::INITIAL_COLLECTOR, state::State};
 
 pub trait Command {
     fn execute(&self, state: Rc<RefCell<State>>) -> bool;
@@ -22,6 +25,11 @@ impl Command for CreateCollectorCreepCommand {
         } else {
             return false;
         };
+        // the creep is already spawning, so don't retry the command if only the role failed
+        if let Err(error) = attach_role(&creep, INITIAL_COLLECTOR) {
+            warn!("{:?}", error);
+        }
+        stats::record_spawn(INITIAL_COLLECTOR);
         state.borrow_mut().initial_collectors.push(creep);
         true
     }
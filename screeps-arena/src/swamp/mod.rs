@@ -5,73 +5,266 @@
 mod commands;
 mod game_state;
 mod role;
+mod run_collector;
+mod run_healer;
 mod run_initial_collector;
 mod run_military;
 mod spawner;
 mod state;
 
-use std::{cell::RefCell, convert::TryFrom, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use eyre::{bail, Result};
 use screeps_arena::{
     game::{self, utils::get_objects_by_prototype},
-    prototypes, Part, StructureContainer,
+    prototypes, Creep, Part, StructureContainer, StructureSpawn,
 };
 
-use crate::global::utilities::{get_creeps, get_spawn, Side};
+use crate::{
+    arena::{Arena, ArenaKind},
+    global::{
+        classifier::classify,
+        commander::{Order, World},
+        healing::HealPlan,
+        influence::InfluenceMap,
+        phase::PhaseSignals,
+        retreat::{get_rally_point, is_retreating},
+        role::get_role_name,
+        stats,
+        strategy::get_strategy,
+        traffic::TrafficManager,
+        utilities::{
+            create_position_object, creep_to_array, get_containers, get_creeps, get_spawn,
+            object_to_creep, Side,
+        },
+    },
+};
 
 use self::{
     commands::{Command, CreateCollectorCreepCommand},
     game_state::GameState,
-    role::Role,
+    role::{create_role_registry, Context, COLLECTOR, HEALER, MILITARY},
     run_military::run_military,
-    state::{State, STATE},
+    state::State,
 };
 
-pub fn run(ticks: u32) -> Result<()> {
-    STATE.with(|state| {
-        let state = state.clone();
-        let mut queue = state
-            .borrow_mut()
-            .queue
-            .drain(..)
-            .collect::<Vec<Box<dyn Command>>>();
-        if ticks == 1 {
-            queue.push(Box::new(CreateCollectorCreepCommand));
-            queue.push(Box::new(CreateCollectorCreepCommand));
-            queue.push(Box::new(CreateCollectorCreepCommand));
+/// How far ahead enemy positions are predicted when judging how close the enemy is to our spawn.
+const PREDICTION_TICKS: u32 = 3;
+
+pub fn run(ticks: u32, state: Rc<RefCell<State>>) -> Result<()> {
+    let my_creeps = get_creeps(true);
+    let enemies = get_creeps(false);
+    state
+        .borrow()
+        .enemy_tracker
+        .borrow_mut()
+        .update(ticks, &enemies);
+    update_phase(ticks, &my_creeps, &enemies, &mut state.borrow_mut());
+
+    let mut queue = state
+        .borrow_mut()
+        .queue
+        .drain(..)
+        .collect::<Vec<Box<dyn Command>>>();
+    if ticks == 1 {
+        queue.push(Box::new(CreateCollectorCreepCommand));
+        queue.push(Box::new(CreateCollectorCreepCommand));
+        queue.push(Box::new(CreateCollectorCreepCommand));
+    }
+
+    let failed_jobs = process_queue(queue, state.clone());
+    state.borrow_mut().queue = failed_jobs;
+
+    let context = create_context(&mut state.borrow_mut(), my_creeps, enemies)?;
+    let roles = create_role_registry();
+    spawner::run_spawner(&roles, &context)?;
+    roles.run_creeps(&context.my_creeps, &context);
+    context.traffic.into_inner().resolve();
+
+    context.game_state.into_inner().save(&context.my_spawn)
+}
+
+pub struct SpawnAndSwamp {
+    state: Option<Rc<RefCell<State>>>,
+}
+
+impl SpawnAndSwamp {
+    pub fn new() -> Self {
+        Self { state: None }
+    }
+
+    /// Returns the match state, creating it the first time it is needed. Creating the state can
+    /// fail if the spawns cannot be found, in which case we try again next tick.
+    fn state(&mut self) -> Result<Rc<RefCell<State>>> {
+        if let Some(state) = &self.state {
+            return Ok(state.clone());
         }
 
-        let failed_jobs = process_queue(queue, state.clone());
-        state.borrow_mut().queue = failed_jobs;
-    });
+        let state = Rc::new(RefCell::new(State::new()?));
+        self.state = Some(state.clone());
+        Ok(state)
+    }
+}
 
-    Ok(())
-
-    // spawner::run_spawner(&my_spawn, &mut game_state)?;
-
-    // for my_creep in &my_creeps {
-    //     let creep_role = Role::try_from(my_creep)?;
-    //     match creep_role {
-    //         Role::None => bail!("trying to run a creep without a role"),
-    //         Role::InitialCollector => {
-    //             if !containers.is_empty() {
-    //                 run_initial_collector::run_initial_collector(
-    //                     my_creep,
-    //                     &my_spawn,
-    //                     &mut game_state,
-    //                     &my_creeps,
-    //                     &containers[0],
-    //                 )?;
-    //             }
-    //         }
-    //         Role::Military => {
-    //             run_military(my_creep, &enemies, &enemy_spawn, &my_spawn, &game_state)?
-    //         }
-    //     }
-    // }
-
-    // game_state.save(&my_spawn)
+impl Arena for SpawnAndSwamp {
+    fn name(&self) -> &'static str {
+        "spawn and swamp"
+    }
+
+    fn kind(&self) -> Option<ArenaKind> {
+        Some(ArenaKind::SpawnAndSwamp)
+    }
+
+    /// Creates the match state up front so a missing spawn is reported before the first tick
+    /// runs.
+    fn init(&mut self) -> Result<()> {
+        self.state()?;
+        Ok(())
+    }
+
+    fn tick(&mut self, tick: u32) -> Result<()> {
+        let state = self.state()?;
+        run(tick, state)
+    }
+}
+
+/// Everything the roles look at this tick. The game state is kept on the spawn between ticks and
+/// starts fresh on the first one. The healers are paired up with the military creeps and the
+/// commander gives its orders here.
+fn create_context(
+    state: &mut State,
+    my_creeps: Vec<Creep>,
+    enemies: Vec<Creep>,
+) -> Result<Context> {
+    let game_state =
+        GameState::load(&state.my_spawn).or_else(|_| GameState::new(&state.my_spawn))?;
+    let containers = get_containers(true)
+        .into_iter()
+        .filter(|container| {
+            !state
+                .spawn_containers
+                .iter()
+                .any(|spawn_container| spawn_container.id() == container.id())
+        })
+        .collect();
+    let collectors = my_creeps
+        .iter()
+        .filter(|creep| matches!(get_role_name(creep), Ok(Some(name)) if name == COLLECTOR))
+        .cloned()
+        .collect();
+    let healers = my_creeps
+        .iter()
+        .filter(|creep| matches!(get_role_name(creep), Ok(Some(name)) if name == HEALER))
+        .cloned()
+        .collect::<Vec<Creep>>();
+    let military = my_creeps
+        .iter()
+        .filter(|creep| matches!(get_role_name(creep), Ok(Some(name)) if name == MILITARY))
+        .cloned()
+        .collect::<Vec<Creep>>();
+    let heal_plan = HealPlan::new(&healers, &my_creeps, &enemies);
+    state.pairings.borrow_mut().update(&healers, &military);
+
+    let influence = InfluenceMap::from_game();
+    let rally_point = get_rally_point(
+        (state.my_spawn.x(), state.my_spawn.y()),
+        (state.enemy_spawn.x(), state.enemy_spawn.y()),
+        &influence,
+    );
+    let orders = give_military_orders(state, &military, &enemies, rally_point)?;
+
+    Ok(Context {
+        my_spawn: state.my_spawn.clone(),
+        enemy_spawn: state.enemy_spawn.clone(),
+        my_creeps,
+        enemies,
+        container: state.spawn_containers.first().cloned(),
+        containers,
+        collectors,
+        game_state: RefCell::new(game_state),
+        influence,
+        rally_point,
+        phase: state.phases.phase(),
+        heal_plan,
+        pairings: state.pairings.clone(),
+        enemy_tracker: state.enemy_tracker.clone(),
+        orders,
+        traffic: RefCell::new(TrafficManager::new()),
+    })
+}
+
+/// The commander's orders for the military creeps that aren't falling back. They all wait at the
+/// rally point until the group is big enough.
+fn give_military_orders(
+    state: &mut State,
+    military: &[Creep],
+    enemies: &[Creep],
+    rally_point: (u8, u8),
+) -> Result<HashMap<String, Order>> {
+    let mut group_size = 0;
+    let mut members = vec![];
+    for creep in military {
+        if creep.spawning() {
+            continue;
+        }
+
+        group_size += 1;
+        if !is_retreating(creep)? {
+            members.push(creep.clone());
+        }
+    }
+
+    if group_size < get_strategy().spawn_and_swamp.group_size_before_attack {
+        return Ok(members
+            .iter()
+            .filter_map(|creep| {
+                let order = Order::MoveTo {
+                    position: rally_point,
+                };
+                Some((creep.id().as_string()?, order))
+            })
+            .collect());
+    }
+
+    let phase = state.phases.phase();
+    Ok(state.commander.tick(&World {
+        military: members,
+        enemies: enemies.to_vec(),
+        home: (state.my_spawn.x(), state.my_spawn.y()),
+        enemy_home: Some((state.enemy_spawn.x(), state.enemy_spawn.y())),
+        flag: None,
+        phase,
+    }))
+}
+
+fn update_phase(tick: u32, my_creeps: &[Creep], enemies: &[Creep], state: &mut State) {
+    let defender_threat_range = get_strategy().spawn_and_swamp.defender_threat_range;
+    let strength = |creeps: &[Creep]| -> u32 {
+        creeps
+            .iter()
+            .map(|creep| classify(creep).threat.score())
+            .sum()
+    };
+    let home = (state.my_spawn.x(), state.my_spawn.y());
+    // an enemy charging the spawn counts as already being where it is headed
+    let approaching_range = state
+        .enemy_tracker
+        .borrow()
+        .closest_approach(home, PREDICTION_TICKS);
+    let enemies_near_home = matches!(approaching_range, Some(range) if range <= defender_threat_range)
+        || enemies.iter().any(|enemy| {
+            classify(enemy).threat.score() > 0
+                && state.my_spawn.get_range_to(enemy) <= defender_threat_range
+        });
+
+    state.phases.update(&PhaseSignals {
+        tick,
+        army_strength: strength(my_creeps),
+        enemy_strength: strength(enemies),
+        // the collectors record every delivery to the spawn
+        energy_collected: stats::get_energy_delivered(),
+        enemies_near_home,
+    });
 }
 
 fn process_queue(queue: Vec<Box<dyn Command>>, state: Rc<RefCell<State>>) -> Vec<Box<dyn Command>> {
@@ -1,103 +1,213 @@
-use std::{cell::Ref, convert::TryFrom};
-
-use eyre::bail;
-use js_sys::Reflect;
-use log::warn;
-use screeps_arena::Creep;
-use serde::{Deserialize, Serialize};
-use wasm_bindgen::JsValue;
-
-#[derive(Serialize, Deserialize)]
-pub enum Role {
-    None,
-    InitialCollector,
-    Military,
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use eyre::Result;
+use screeps_arena::{Creep, Part, StructureContainer, StructureSpawn};
+
+use crate::global::{
+    commander::Order,
+    healing::HealPlan,
+    influence::InfluenceMap,
+    pairing::Pairings,
+    phase::Phase,
+    role::{RoleBehavior, RoleRegistry},
+    strategy::get_strategy,
+    tracker::EnemyTracker,
+    traffic::TrafficManager,
+};
+
+use super::{
+    game_state::GameState, run_collector::run_collector, run_healer::run_healer,
+    run_initial_collector::run_initial_collector, run_military::run_military,
+};
+
+pub const INITIAL_COLLECTOR: &str = "InitialCollector";
+pub const COLLECTOR: &str = "Collector";
+pub const HEALER: &str = "Healer";
+pub const MILITARY: &str = "Military";
+
+pub struct Context {
+    pub my_spawn: StructureSpawn,
+    pub enemy_spawn: StructureSpawn,
+    pub my_creeps: Vec<Creep>,
+    pub enemies: Vec<Creep>,
+    pub container: Option<StructureContainer>,
+    /// Containers with energy away from the spawn, the initial collectors empty the ones next
+    /// to it.
+    pub containers: Vec<StructureContainer>,
+    pub collectors: Vec<Creep>,
+    pub game_state: RefCell<GameState>,
+    pub influence: InfluenceMap,
+    /// Where retreating military creeps fall back to.
+    pub rally_point: (u8, u8),
+    pub phase: Phase,
+    /// Shared by every healer so they don't all pick the same patient.
+    pub heal_plan: HealPlan,
+    pub pairings: Rc<RefCell<Pairings>>,
+    pub enemy_tracker: Rc<RefCell<EnemyTracker>>,
+    /// What the commander wants every military creep to do this tick, by creep id.
+    pub orders: HashMap<String, Order>,
+    /// Roles submit their moves here, resolve it once every creep has run.
+    pub traffic: RefCell<TrafficManager>,
 }
 
-impl Role {
-    pub fn add_to_creep(&self, creep: &Creep) -> eyre::Result<()> {
-        let role_string = self.to_string();
+pub fn create_role_registry() -> RoleRegistry<Context> {
+    RoleRegistry::new()
+        .register(InitialCollectorRole)
+        .register(CollectorRole)
+        .register(HealerRole)
+        .register(MilitaryRole)
+}
 
-        if let Err(_) = Reflect::set(
-            creep,
-            &JsValue::from_str("role"),
-            &JsValue::from_str(&role_string),
-        ) {
-            bail!("Error setting role on creep");
+struct InitialCollectorRole;
+
+impl RoleBehavior for InitialCollectorRole {
+    type Context = Context;
+
+    fn name(&self) -> &'static str {
+        INITIAL_COLLECTOR
+    }
+
+    fn body(&self) -> Vec<Part> {
+        vec![Part::Move, Part::Carry]
+    }
+
+    fn run(&self, creep: &Creep, context: &Context) -> Result<()> {
+        match &context.container {
+            Some(container) => run_initial_collector(
+                creep,
+                &context.my_spawn,
+                &mut context.game_state.borrow_mut(),
+                &context.my_creeps,
+                container,
+                &mut context.traffic.borrow_mut(),
+            ),
+            None => Ok(()),
         }
+    }
 
-        Ok(())
+    fn spawn_priority(&self) -> u8 {
+        3
     }
-}
 
-impl Default for Role {
-    fn default() -> Self {
-        Self::None
+    fn wanted(&self, context: &Context) -> u8 {
+        context.game_state.borrow().want_initial_collectors
     }
 }
 
-impl ToString for Role {
-    fn to_string(&self) -> String {
-        match self {
-            Role::None => "None",
-            Role::InitialCollector => "InitialCollector",
-            Role::Military => "Military",
-        }
-        .to_owned()
+struct CollectorRole;
+
+impl RoleBehavior for CollectorRole {
+    type Context = Context;
+
+    fn name(&self) -> &'static str {
+        COLLECTOR
+    }
+
+    fn body(&self) -> Vec<Part> {
+        vec![Part::Carry, Part::Move]
+    }
+
+    fn run(&self, creep: &Creep, context: &Context) -> Result<()> {
+        run_collector(
+            creep,
+            &context.my_spawn,
+            &context.containers,
+            &context.collectors,
+            &context.enemies,
+            &context.influence,
+            &mut context.traffic.borrow_mut(),
+        )
+    }
+
+    fn spawn_priority(&self) -> u8 {
+        2
+    }
+
+    fn wanted(&self, _context: &Context) -> u8 {
+        get_strategy().spawn_and_swamp.want_collectors
     }
 }
 
-impl TryFrom<&Creep> for Role {
-    type Error = eyre::Error;
-
-    fn try_from(creep: &Creep) -> Result<Self, Self::Error> {
-        // if let Ok(js_role) = Reflect::get(creep, &JsValue::from_str("role")) {
-        //     if let Some(role_string) = js_role.as_string() {
-        //     } else {
-        //         warn!("Could not get role string off of creep object");
-        //         panic!();
-        //     }
-        // }
-        if let Ok(js_role) = Reflect::get(creep, &JsValue::from_str("role")) {
-            let role_string = js_role
-                .as_string()
-                .ok_or(eyre::eyre!("Error getting role string from creep object"))?;
-            Ok(match role_string.as_str() {
-                "None" => Self::None,
-                "InitialCollector" => Self::InitialCollector,
-                "Military" => Self::Military,
-                _ => bail!("Unknown role"),
-            })
-        } else {
-            bail!("Error getting the js value role from creep object");
+struct HealerRole;
+
+impl RoleBehavior for HealerRole {
+    type Context = Context;
+
+    fn name(&self) -> &'static str {
+        HEALER
+    }
+
+    fn body(&self) -> Vec<Part> {
+        vec![Part::Move, Part::Move, Part::Heal, Part::Heal]
+    }
+
+    fn run(&self, creep: &Creep, context: &Context) -> Result<()> {
+        let pairings = context.pairings.borrow();
+        let partners = pairings.partners(creep, &context.my_creeps);
+        run_healer(
+            creep,
+            &context.heal_plan,
+            &partners,
+            &context.enemies,
+            &context.my_spawn,
+            &mut context.traffic.borrow_mut(),
+        )
+    }
+
+    fn spawn_priority(&self) -> u8 {
+        1
+    }
+
+    /// Nobody gets hurt in the opening.
+    fn wanted(&self, context: &Context) -> u8 {
+        match context.phase {
+            Phase::Opening => 0,
+            _ => get_strategy().spawn_and_swamp.want_healers,
         }
     }
-    // fn try_from(creep: &Creep) -> Self {
-    // }
 }
 
-pub enum EnemyRole {
-    Healer,
-    Unknown,
-    Collector,
-    Melee,
-    Ranger,
-}
+struct MilitaryRole;
 
-impl TryFrom<&Creep> for EnemyRole {
-    type Error = eyre::Error;
-
-    fn try_from(creep: &Creep) -> Result<Self, Self::Error> {
-        for body_part in creep.body().iter() {
-            match body_part.part() {
-                screeps_arena::Part::Carry => return Ok(Self::Collector),
-                screeps_arena::Part::Attack => return Ok(Self::Melee),
-                screeps_arena::Part::RangedAttack => return Ok(Self::Ranger),
-                screeps_arena::Part::Heal => return Ok(Self::Healer),
-                _ => continue,
-            }
-        }
+impl RoleBehavior for MilitaryRole {
+    type Context = Context;
+
+    fn name(&self) -> &'static str {
+        MILITARY
+    }
+
+    fn body(&self) -> Vec<Part> {
+        vec![
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::Move,
+            Part::RangedAttack,
+            Part::Heal,
+        ]
+    }
+
+    fn run(&self, creep: &Creep, context: &Context) -> Result<()> {
+        run_military(creep, context)
+    }
 
-        Ok(Self::Unknown)
+    fn spawn_priority(&self) -> u8 {
+        0
+    }
+
+    /// The opening's energy goes to the collectors.
+    fn wanted(&self, context: &Context) -> u8 {
+        match context.phase {
+            Phase::Opening => 0,
+            _ => get_strategy().spawn_and_swamp.want_attackers,
+        }
     }
 }
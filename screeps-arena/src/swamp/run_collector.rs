@@ -1,30 +1,68 @@
-use screeps_arena::{Creep, ReturnCode, StructureSpawn};
+use eyre::Result;
+use screeps_arena::{Creep, ResourceType, ReturnCode, StructureContainer, StructureSpawn};
 
-use crate::global::utilities::{containers_to_array, get_containers, object_to_container};
+use crate::global::{
+    influence::InfluenceMap,
+    routing::{choose_container, flee_to_spawn, should_flee},
+    stats,
+    traffic::{TrafficManager, WORKER_PRIORITY},
+};
+
+/// Empties the containers around the map into the spawn, dropping back to the spawn whenever a
+/// raider gets close.
+pub fn run_collector(
+    creep: &Creep,
+    spawn: &StructureSpawn,
+    containers: &[StructureContainer],
+    other_collectors: &[Creep],
+    enemies: &[Creep],
+    influence: &InfluenceMap,
+    traffic: &mut TrafficManager,
+) -> Result<()> {
+    if should_flee(creep, enemies) {
+        return flee_to_spawn(creep, spawn, influence, traffic);
+    }
 
-pub fn run_collector(creep: &Creep, spawn: &StructureSpawn) {
     if have_energy(creep) {
-        if creep.transfer(spawn, screeps_arena::ResourceType::Energy, None)
-            == ReturnCode::NotInRange
-        {
-            creep.move_to(spawn, None);
+        match creep.transfer(spawn, ResourceType::Energy, None) {
+            ReturnCode::Ok => stats::record_energy_delivered(
+                creep.store().get_used_capacity(Some(ResourceType::Energy)),
+            ),
+            ReturnCode::NotInRange => {
+                return traffic.request_move_toward(
+                    creep,
+                    spawn,
+                    Some(&influence.move_options(creep.hits())),
+                    WORKER_PRIORITY,
+                );
+            }
+            _ => (),
         }
 
-        return;
+        traffic.idle(creep);
+        return Ok(());
     }
 
-    let containers = get_containers(true);
-    if let Some(closest_container) =
-        creep.find_closest_by_path(&containers_to_array(&containers), None)
-    {
-        let container = object_to_container(&closest_container).unwrap();
-        if creep.withdraw(&container, screeps_arena::ResourceType::Energy, None)
-            == ReturnCode::NotInRange
-        {
-            creep.move_to(&container, None);
+    if let Some(container) = choose_container(creep, containers, other_collectors, influence)? {
+        match creep.withdraw(container, ResourceType::Energy, None) {
+            ReturnCode::Ok => stats::record_energy_withdrawn(
+                creep.store().get_free_capacity(Some(ResourceType::Energy)),
+            ),
+            ReturnCode::NotInRange => {
+                return traffic.request_move_toward(
+                    creep,
+                    container,
+                    Some(&influence.move_options(creep.hits())),
+                    WORKER_PRIORITY,
+                );
+            }
+            _ => (),
         }
+
+        traffic.idle(creep);
+        Ok(())
     } else {
-        creep.move_to(spawn, None);
+        traffic.request_move_toward(creep, spawn, None, WORKER_PRIORITY)
     }
 }
 
@@ -1,39 +1,35 @@
-use crate::global::utilities::{create_position_object, creep_to_array, object_to_creep};
+use crate::global::{
+    healing::{execute_heal, HealPlan},
+    pairing::run_bodyguard,
+    traffic::{TrafficManager, HEALER_PRIORITY},
+    utilities::create_position_object,
+};
+use eyre::Result;
 use js_sys::Object;
-use screeps_arena::{Creep, ReturnCode, StructureSpawn};
+use screeps_arena::{Creep, StructureSpawn};
 
-pub fn run_healer(creep: &Creep, friends: &[Creep], spawn: &StructureSpawn) {
-    if is_damaged(creep) {
-        creep.heal(creep);
-    } else {
-        let damaged_friends = get_damaged_friends(friends);
-        if let Some(closest_damaged_friend) =
-            creep.find_closest_by_path(&creep_to_array(&damaged_friends), None)
-        {
-            let closest_damaged_friend = object_to_creep(&closest_damaged_friend).unwrap();
-            if creep.heal(&closest_damaged_friend) == ReturnCode::NotInRange {
-                creep.ranged_heal(&closest_damaged_friend);
-                creep.move_to(&closest_damaged_friend, None);
-            }
-        } else {
+/// Follows the healer's part of the tick's heal plan. Without one it guards its partners, or
+/// waits next to the spawn until it has been paired with a military creep.
+pub fn run_healer(
+    creep: &Creep,
+    heal_plan: &HealPlan,
+    partners: &[&Creep],
+    enemies: &[Creep],
+    spawn: &StructureSpawn,
+    traffic: &mut TrafficManager,
+) -> Result<()> {
+    match heal_plan.order_for(creep) {
+        Some(order) => execute_heal(creep, order, traffic),
+        None if !partners.is_empty() => run_bodyguard(creep, partners, enemies, traffic),
+        None => {
             let staging_area = get_staging_area(spawn);
-            creep.move_to(&staging_area, None);
+            #[cfg(feature = "debug-visuals")]
+            crate::global::visuals::draw_staging_area(&staging_area);
+            traffic.request_move_toward(creep, &staging_area, None, HEALER_PRIORITY)
         }
     }
 }
 
-fn is_damaged(creep: &Creep) -> bool {
-    creep.hits() < creep.hits_max()
-}
-
-fn get_damaged_friends(creeps: &[Creep]) -> Vec<Creep> {
-    creeps
-        .iter()
-        .filter(|creep| is_damaged(creep))
-        .map(|creep| creep.clone())
-        .collect()
-}
-
 fn get_staging_area(spawn: &StructureSpawn) -> Object {
     let x = if spawn.x() < 20 {
         spawn.x() + 3
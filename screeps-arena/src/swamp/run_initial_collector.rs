@@ -1,7 +1,11 @@
 use eyre::{bail, Result};
-use screeps_arena::{Creep, ResourceType, StructureContainer, StructureSpawn};
+use screeps_arena::{Creep, ResourceType, ReturnCode, StructureContainer, StructureSpawn};
 
-use crate::global::utilities::{create_position_object, get_creep_id};
+use crate::global::{
+    stats,
+    traffic::TrafficManager,
+    utilities::{create_position_object, get_creep_id},
+};
 
 use super::game_state::GameState;
 
@@ -11,12 +15,18 @@ pub fn run_initial_collector(
     game_state: &mut GameState,
     my_creeps: &Vec<Creep>,
     container: &StructureContainer,
+    traffic: &mut TrafficManager,
 ) -> Result<()> {
     let id = get_creep_id(creep)?;
     let collector_index = get_collector_index(game_state, id)?;
     let (x, y) = game_state.initial_collector_positions[collector_index];
-    let position = create_position_object(x, y);
-    creep.move_to(&position, None);
+    if (creep.x(), creep.y()) == (x, y) {
+        traffic.idle(creep);
+    } else {
+        // the collector going furthest from the spawn has to get past the others first
+        let priority = (game_state.initial_collector_positions.len() - collector_index) as u8;
+        traffic.request_move_toward(creep, &create_position_object(x, y), None, priority)?;
+    }
 
     if !game_state.have_all_initial_collectors() {
         return Ok(());
@@ -24,11 +34,17 @@ pub fn run_initial_collector(
 
     match collector_index {
         2 => {
-            creep.transfer(spawn, ResourceType::Energy, None);
+            let energy = creep.store().get_used_capacity(Some(ResourceType::Energy));
+            if creep.transfer(spawn, ResourceType::Energy, None) == ReturnCode::Ok {
+                stats::record_energy_delivered(energy);
+            }
         }
         1 => transfer_to_next_creep(&game_state, 2, my_creeps, creep)?,
         0 => {
-            creep.withdraw(container, ResourceType::Energy, None);
+            let free_capacity = creep.store().get_free_capacity(Some(ResourceType::Energy));
+            if creep.withdraw(container, ResourceType::Energy, None) == ReturnCode::Ok {
+                stats::record_energy_withdrawn(free_capacity);
+            }
             transfer_to_next_creep(&game_state, 1, my_creeps, creep)?;
         }
         _ => bail!("collector index is not what we expected: {collector_index}"),
@@ -67,7 +83,7 @@ fn transfer_to_next_creep(
 
     let next_creep = my_creeps
         .iter()
-        .find(|creep| get_creep_id(creep).unwrap() == next_creep_id)
+        .find(|creep| get_creep_id(creep).map_or(false, |id| id == next_creep_id))
         .ok_or(eyre::eyre!("Could not find next creep in line"))?;
 
     creep.transfer(next_creep, ResourceType::Energy, None);
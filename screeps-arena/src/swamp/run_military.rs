@@ -1,30 +1,55 @@
 use std::cell::Ref;
 
 use eyre::{bail, Result};
-use js_sys::{Array, Reflect};
+use js_sys::Reflect;
 use log::warn;
 use screeps_arena::{game::utils::get_object_by_id, Creep, StructureSpawn};
 use wasm_bindgen::JsValue;
 use web_sys::console::warn;
 
-use super::game_state::GameState;
-
-pub fn run_military(
-    creep: &Creep,
-    enemies: &Vec<Creep>,
-    enemy_spawn: &StructureSpawn,
-    my_spawn: &StructureSpawn,
-    _game_state: &GameState,
-) -> Result<()> {
+use crate::global::{
+    classifier::pick_target,
+    commander::execute_order,
+    ranged::fire_ranged,
+    retreat::{retreat_to, should_retreat},
+};
+
+use super::role::Context;
+
+/// Falls back when badly hurt, otherwise carries out the commander's order. Creeps without one
+/// shoot at their target from where they are.
+pub fn run_military(creep: &Creep, context: &Context) -> Result<()> {
+    #[cfg(feature = "debug-visuals")]
+    crate::global::visuals::draw_role_label(creep, "military");
+
+    let enemies = &context.enemies;
+    let mut traffic = context.traffic.borrow_mut();
+    if should_retreat(creep, enemies)? {
+        return retreat_to(creep, context.rally_point, &context.influence, &mut traffic);
+    }
     if creep.hits() < creep.hits_max() {
         creep.heal(creep);
-        creep.move_to(my_spawn, None);
-        return Ok(());
     }
 
+    let order = creep
+        .id()
+        .as_string()
+        .and_then(|id| context.orders.get(&id));
+    match order {
+        Some(order) => execute_order(
+            creep,
+            order,
+            enemies,
+            &context.enemy_tracker.borrow(),
+            &mut traffic,
+        )?,
+        None => traffic.idle(creep),
+    }
     if enemies.is_empty() {
-        creep.move_to(&enemy_spawn, None);
-        creep.ranged_attack(enemy_spawn);
+        creep.ranged_attack(&context.enemy_spawn);
+        return Ok(());
+    }
+    if order.is_some() {
         return Ok(());
     }
 
@@ -38,8 +63,13 @@ pub fn run_military(
     };
 
     if let Some(enemy) = get_enemy_by_id(target_id, enemies)? {
-        creep.move_to(enemy, None);
-        creep.ranged_attack(enemy);
+        #[cfg(feature = "debug-visuals")]
+        {
+            crate::global::visuals::draw_target_line(creep, enemy.x(), enemy.y());
+            crate::global::visuals::draw_planned_path(creep, enemy);
+        }
+
+        fire_ranged(creep, Some(enemy), enemies);
     } else {
         unassign_target(creep)?;
     }
@@ -60,23 +90,16 @@ fn get_assigned_target(creep: &Creep) -> Result<Option<f64>> {
 }
 
 fn find_target(enemies: &Vec<Creep>, creep: &Creep) -> Result<Option<f64>> {
-    let enemies_array = Array::new();
-    for enemy in enemies {
-        enemies_array.push(enemy);
-    }
-
-    if let Some(closest_creep) = creep.find_closest_by_path(&enemies_array, None) {
-        if let Ok(js_id) = Reflect::get(&closest_creep, &JsValue::from_str("id")) {
-            let id = js_id
+    match pick_target(creep, enemies) {
+        Some(target) => {
+            let id = target
+                .id()
                 .as_string()
                 .ok_or(eyre::eyre!("Error getting id"))?
                 .parse::<f64>()?;
             Ok(Some(id))
-        } else {
-            Ok(None)
         }
-    } else {
-        Ok(None)
+        None => Ok(None),
     }
 }
 
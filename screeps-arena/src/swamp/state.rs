@@ -1,15 +1,17 @@
 use std::{cell::RefCell, rc::Rc};
 
+use eyre::Result;
 use screeps_arena::{
     game::utils::get_objects_by_prototype, prototypes, Creep, OwnedStructureProperties,
     StructureContainer, StructureSpawn,
 };
 
-use super::commands::Command;
+use crate::global::{
+    commander::Commander, pairing::Pairings, phase::PhaseManager, stats, strategy::get_strategy,
+    tracker::EnemyTracker,
+};
 
-thread_local! {
-    pub static STATE: Rc<RefCell<State>> = Rc::new(RefCell::new(State::default()));
-}
+use super::commands::Command;
 
 pub struct State {
     pub my_spawn: StructureSpawn,
@@ -18,10 +20,15 @@ pub struct State {
     pub spawn_containers: Vec<StructureContainer>,
     pub initial_collectors: Vec<Creep>,
     pub queue: Vec<Box<dyn Command>>,
+    pub enemy_tracker: Rc<RefCell<EnemyTracker>>,
+    pub pairings: Rc<RefCell<Pairings>>,
+    pub phases: PhaseManager,
+    /// Splits the military into squads, kept between ticks so squads keep their members.
+    pub commander: Commander,
 }
 
-impl Default for State {
-    fn default() -> Self {
+impl State {
+    pub fn new() -> Result<Self> {
         let mut my_spawn = None;
         let mut enemy_spawn = None;
 
@@ -35,7 +42,9 @@ impl Default for State {
                 }
             });
 
-        let my_spawn_x = my_spawn.as_ref().unwrap().x();
+        let my_spawn = my_spawn.ok_or(eyre::eyre!("Could not find my spawn"))?;
+        let enemy_spawn = enemy_spawn.ok_or(eyre::eyre!("Could not find enemy spawn"))?;
+        let my_spawn_x = my_spawn.x();
 
         let my_side = if my_spawn_x < 50 {
             Side::Left
@@ -52,14 +61,19 @@ impl Default for State {
             })
             .collect::<Vec<StructureContainer>>();
 
-        Self {
-            my_spawn: my_spawn.unwrap(),
-            enemy_spawn: enemy_spawn.unwrap(),
+        Ok(Self {
+            my_spawn,
+            enemy_spawn,
             my_side,
             spawn_containers: containers,
             initial_collectors: vec![],
             queue: vec![],
-        }
+            enemy_tracker: Rc::new(RefCell::new(EnemyTracker::new())),
+            pairings: Rc::new(RefCell::new(Pairings::new())),
+            phases: PhaseManager::new(get_strategy().spawn_and_swamp.endgame_tick)
+                .on_change(|_from, to, tick| stats::record_phase(&format!("{:?}", to), tick)),
+            commander: Commander::new(),
+        })
     }
 }
 
@@ -1,5 +1,7 @@
+use std::convert::TryFrom;
+
+use eyre::{bail, Result};
 use js_sys::Reflect;
-use log::warn;
 use screeps_arena::{
     game::utils::{create_construction_site, get_objects_by_prototype},
     prototypes::{self, PrototypeConstant},
@@ -7,20 +9,22 @@ use screeps_arena::{
 };
 use wasm_bindgen::JsValue;
 
+use crate::{arena::Arena, global::utilities::log_creep_error};
+
 enum Role {
     Collector(StructureContainer),
     Builder(Option<ConstructionSite>),
 }
 
 impl Role {
-    pub fn new_role(creep: &Creep) -> Option<Self> {
-        if creep.store().get_used_capacity(None) == 0 {
-            Some(Self::Collector(get_container()))
+    pub fn new_role(creep: &Creep) -> Result<Option<Self>> {
+        Ok(if creep.store().get_used_capacity(None) == 0 {
+            Some(Self::Collector(get_container()?))
         } else if creep.store().get_free_capacity(None) == 0 {
             Some(Self::Builder(get_construction_site()))
         } else {
             None
-        }
+        })
     }
 }
 
@@ -33,85 +37,100 @@ impl Into<JsValue> for Role {
     }
 }
 
-impl From<&Creep> for Role {
-    fn from(creep: &Creep) -> Self {
+impl TryFrom<&Creep> for Role {
+    type Error = eyre::Error;
+
+    fn try_from(creep: &Creep) -> Result<Self, Self::Error> {
         if let Ok(role_string) = Reflect::get(creep, &JsValue::from_str("role")) {
-            match js_value_to_string(role_string).as_str() {
-                "Collector" => Self::Collector(get_container()),
+            Ok(match js_value_to_string(role_string)?.as_str() {
+                "Collector" => Self::Collector(get_container()?),
                 "Builder" => Self::Builder(get_construction_site()),
-                _ => {
-                    warn!("Error, attempted to create role from unknown string");
-                    panic!();
-                }
-            }
+                _ => bail!("Error, attempted to create role from unknown string"),
+            })
         } else {
-            warn!("Error getting role from creep object");
-            panic!();
+            bail!("Error getting role from creep object");
         }
     }
 }
 
 #[allow(dead_code)]
-pub fn run(tick: u32) {
+pub fn run(tick: u32) -> Result<()> {
     if tick == 1 {
         let tower_position = (50, 55);
 
-        create_construction_site(
+        if let Err(error) = create_construction_site(
             tower_position.0,
             tower_position.1,
             prototypes::STRUCTURE_TOWER.prototype(),
-        )
-        .unwrap();
+        ) {
+            bail!("Error creating tower construction site: {:?}", error);
+        }
     }
 
     for creep in get_objects_by_prototype(prototypes::CREEP) {
-        if let Some(new_role) = Role::new_role(&creep) {
-            store_role_on_creep(&creep, new_role);
-        }
+        let result = run_creep(&creep);
+        log_creep_error(&creep, result);
+    }
 
-        match Role::from(&creep) {
-            Role::Collector(container) => {
-                let result = creep.withdraw(&container, screeps_arena::ResourceType::Energy, None);
-                if result == ReturnCode::NotInRange {
-                    creep.move_to(&container, None);
-                }
+    Ok(())
+}
+
+pub struct Construction;
+
+impl Arena for Construction {
+    fn name(&self) -> &'static str {
+        "tutorial construction"
+    }
+
+    fn tick(&mut self, tick: u32) -> Result<()> {
+        run(tick)
+    }
+}
+
+fn run_creep(creep: &Creep) -> Result<()> {
+    if let Some(new_role) = Role::new_role(creep)? {
+        store_role_on_creep(creep, new_role)?;
+    }
+
+    match Role::try_from(creep)? {
+        Role::Collector(container) => {
+            let result = creep.withdraw(&container, screeps_arena::ResourceType::Energy, None);
+            if result == ReturnCode::NotInRange {
+                creep.move_to(&container, None);
             }
-            Role::Builder(construction_site) => {
-                if let Some(construction_site) = construction_site {
-                    let result = creep.build(&construction_site);
-                    if result == ReturnCode::NotInRange {
-                        creep.move_to(&construction_site, None);
-                    }
+        }
+        Role::Builder(construction_site) => {
+            if let Some(construction_site) = construction_site {
+                let result = creep.build(&construction_site);
+                if result == ReturnCode::NotInRange {
+                    creep.move_to(&construction_site, None);
                 }
             }
         }
     }
-}
 
-fn store_role_on_creep(creep: &Creep, role: Role) {
-    if let Err(error) = Reflect::set(creep, &JsValue::from_str("role"), &role.into()) {
-        warn!("Error setting role on Creep: {:?}", error);
-        panic!();
-    }
+    Ok(())
 }
 
-fn js_value_to_string(js_value: JsValue) -> String {
-    if !js_value.is_string() {
-        warn!("JsValue is not a string");
-        panic!();
+fn store_role_on_creep(creep: &Creep, role: Role) -> Result<()> {
+    if let Err(error) = Reflect::set(creep, &JsValue::from_str("role"), &role.into()) {
+        bail!("Error setting role on Creep: {:?}", error);
     }
 
-    js_value.as_string().unwrap()
+    Ok(())
 }
 
-fn get_container() -> StructureContainer {
-    let containers = get_objects_by_prototype(prototypes::STRUCTURE_CONTAINER);
-    if containers.is_empty() {
-        warn!("Could not find any containers");
-        panic!();
-    }
+fn js_value_to_string(js_value: JsValue) -> Result<String> {
+    js_value
+        .as_string()
+        .ok_or(eyre::eyre!("JsValue is not a string"))
+}
 
-    containers[0].clone()
+fn get_container() -> Result<StructureContainer> {
+    get_objects_by_prototype(prototypes::STRUCTURE_CONTAINER)
+        .into_iter()
+        .next()
+        .ok_or(eyre::eyre!("Could not find any containers"))
 }
 
 fn get_construction_site() -> Option<ConstructionSite> {
@@ -1,7 +1,11 @@
+use eyre::{bail, Result};
+use log::warn;
 use screeps_arena::{game::utils::get_objects_by_prototype, prototypes, Creep, ReturnCode};
 
+use crate::arena::Arena;
+
 #[allow(dead_code)]
-pub fn run() {
+pub fn run() -> Result<()> {
     let mut fighter = None;
     let mut ranger = None;
     let mut healer = None;
@@ -16,16 +20,17 @@ pub fn run() {
             }
 
             match get_creep_type(&creep) {
-                CreepType::Fighter => fighter = Some(creep),
-                CreepType::Ranger => ranger = Some(creep),
-                CreepType::Healer => healer = Some(creep),
+                Ok(CreepType::Fighter) => fighter = Some(creep),
+                Ok(CreepType::Ranger) => ranger = Some(creep),
+                Ok(CreepType::Healer) => healer = Some(creep),
+                Err(error) => warn!("Error running creep {}: {:?}", creep.id(), error),
             }
         });
 
     let enemy = if let Some(enemy) = enemy {
         enemy
     } else {
-        return;
+        return Ok(());
     };
 
     if let Some(fighter) = &fighter {
@@ -57,6 +62,20 @@ pub fn run() {
             healer.heal(&healer);
         }
     }
+
+    Ok(())
+}
+
+pub struct CreepsBodies;
+
+impl Arena for CreepsBodies {
+    fn name(&self) -> &'static str {
+        "tutorial creeps bodies"
+    }
+
+    fn tick(&mut self, _tick: u32) -> Result<()> {
+        run()
+    }
 }
 
 enum CreepType {
@@ -65,17 +84,17 @@ enum CreepType {
     Healer,
 }
 
-fn get_creep_type(creep: &Creep) -> CreepType {
+fn get_creep_type(creep: &Creep) -> Result<CreepType> {
     for body_part in creep.body() {
         match body_part.part() {
-            screeps_arena::Part::Attack => return CreepType::Fighter,
-            screeps_arena::Part::RangedAttack => return CreepType::Ranger,
-            screeps_arena::Part::Heal => return CreepType::Healer,
+            screeps_arena::Part::Attack => return Ok(CreepType::Fighter),
+            screeps_arena::Part::RangedAttack => return Ok(CreepType::Ranger),
+            screeps_arena::Part::Heal => return Ok(CreepType::Healer),
             _ => continue,
         }
     }
 
-    unreachable!()
+    bail!("Could not determine creep type")
 }
 
 fn has_been_hurt(creep: &Creep) -> bool {
@@ -1,53 +1,213 @@
+use eyre::{bail, Result};
 use js_sys::Reflect;
-use log::warn;
+use log::{info, warn};
 use screeps_arena::{
     game::utils::get_objects_by_prototype,
     prototypes::{self},
-    Creep, Part, ReturnCode, StructureSpawn,
+    Creep, OwnedStructureProperties, Part, ResourceType, ReturnCode, StructureSpawn,
+    StructureTower,
 };
-use std::{collections::HashMap, hash::Hash};
+use std::{cell::RefCell, collections::HashMap, convert::TryFrom, rc::Rc};
 use wasm_bindgen::JsValue;
 
-const DESIRED_SPAWN_REFILLER_COUNT: u8 = 2;
-const DESIRED_FIGHTER_COUNT: u8 = 1;
-const DESIRED_RANGER_COUNT: u8 = 5;
-
-#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
-enum Role {
-    SpawnRefiller,
-    Builder,
-    TurretRefiller,
-    Fighter,
-    Ranger,
-    Healer,
-    None,
+use crate::{
+    arena::Arena,
+    global::{
+        body::CreepStats,
+        pairing::{run_bodyguard, Pairings},
+        role::{RoleBehavior, RoleRegistry},
+        stats,
+        strategy::get_strategy,
+        tower::TowerController,
+        traffic::TrafficManager,
+        utilities::{containers_to_array, get_containers, object_to_container},
+    },
+};
+
+/// Enemies this close to a tower are attacking it, its refiller goes there first.
+const TOWER_UNDER_ATTACK_RANGE: u8 = 5;
+/// Towers shoot at enemies this close to our spawn even when they are low on energy.
+const SPAWN_DANGER_RANGE: u8 = 5;
+const RANGER: &str = "Ranger";
+const FIGHTER: &str = "Fighter";
+const HEALER: &str = "Healer";
+
+struct Context {
+    spawn: StructureSpawn,
+    my_creeps: Vec<Creep>,
+    enemy_creeps: Vec<Creep>,
+    towers: Vec<StructureTower>,
+    pairings: Rc<RefCell<Pairings>>,
+    /// The healers submit their moves here, resolve it once every creep has run.
+    traffic: RefCell<TrafficManager>,
+}
+
+struct SpawnRefillerRole;
+
+impl RoleBehavior for SpawnRefillerRole {
+    type Context = Context;
+
+    fn name(&self) -> &'static str {
+        "SpawnRefiller"
+    }
+
+    fn body(&self) -> Vec<Part> {
+        vec![Part::Carry, Part::Work, Part::Move, Part::Move]
+    }
+
+    fn run(&self, creep: &Creep, context: &Context) -> Result<()> {
+        assign_harvest_state(creep)?;
+        run_spawn_refiller_role(creep, &context.spawn)
+    }
+
+    fn spawn_priority(&self) -> u8 {
+        4
+    }
+
+    fn wanted(&self, _context: &Context) -> u8 {
+        get_strategy().final_test.desired_spawn_refiller_count
+    }
 }
 
-impl From<&Creep> for Role {
-    fn from(creep: &Creep) -> Self {
-        match get_custom_string_from_creep(creep, "role").as_str() {
-            "SpawnRefiller" => Self::SpawnRefiller,
-            "Builder" => Self::Builder,
-            "TurretRefiller" => Self::TurretRefiller,
-            "Fighter" => Self::Fighter,
-            "Ranger" => Self::Ranger,
-            "Healer" => Self::Healer,
-            _ => Self::None,
+struct TowerRefillerRole;
+
+impl RoleBehavior for TowerRefillerRole {
+    type Context = Context;
+
+    fn name(&self) -> &'static str {
+        "TowerRefiller"
+    }
+
+    fn body(&self) -> Vec<Part> {
+        vec![Part::Carry, Part::Carry, Part::Move, Part::Move]
+    }
+
+    fn run(&self, creep: &Creep, context: &Context) -> Result<()> {
+        assign_harvest_state(creep)?;
+        run_tower_refiller_role(creep, context)
+    }
+
+    fn spawn_priority(&self) -> u8 {
+        3
+    }
+
+    /// Only spawned once a tower is running low, towers don't use energy until they fire.
+    fn wanted(&self, context: &Context) -> u8 {
+        if context.towers.iter().any(needs_energy) {
+            get_strategy().final_test.desired_tower_refiller_count
+        } else {
+            0
         }
     }
 }
 
-impl Into<JsValue> for Role {
-    fn into(self) -> JsValue {
-        JsValue::from_str(match self {
-            Role::SpawnRefiller => "SpawnRefiller",
-            Role::Builder => "Builder",
-            Role::TurretRefiller => "TurretRefiller",
-            Role::Fighter => "Fighter",
-            Role::Ranger => "Ranger",
-            Role::Healer => "Healer",
-            Role::None => "None",
-        })
+struct BuilderRole;
+
+impl RoleBehavior for BuilderRole {
+    type Context = Context;
+
+    fn name(&self) -> &'static str {
+        "Builder"
+    }
+
+    fn body(&self) -> Vec<Part> {
+        vec![Part::Carry, Part::Work, Part::Move, Part::Move]
+    }
+
+    fn run(&self, creep: &Creep, _context: &Context) -> Result<()> {
+        assign_harvest_state(creep)?;
+        run_builder(creep)
+    }
+
+    fn spawn_priority(&self) -> u8 {
+        0
+    }
+}
+
+struct RangerRole;
+
+impl RoleBehavior for RangerRole {
+    type Context = Context;
+
+    fn name(&self) -> &'static str {
+        RANGER
+    }
+
+    fn body(&self) -> Vec<Part> {
+        vec![Part::RangedAttack, Part::Tough, Part::Move, Part::Move]
+    }
+
+    fn run(&self, creep: &Creep, context: &Context) -> Result<()> {
+        run_ranger(creep, &context.enemy_creeps);
+        Ok(())
+    }
+
+    fn spawn_priority(&self) -> u8 {
+        2
+    }
+
+    fn wanted(&self, _context: &Context) -> u8 {
+        get_strategy().final_test.desired_ranger_count
+    }
+}
+
+struct FighterRole;
+
+impl RoleBehavior for FighterRole {
+    type Context = Context;
+
+    fn name(&self) -> &'static str {
+        FIGHTER
+    }
+
+    fn body(&self) -> Vec<Part> {
+        vec![Part::Attack, Part::Tough, Part::Move, Part::Move]
+    }
+
+    fn run(&self, creep: &Creep, context: &Context) -> Result<()> {
+        run_fighter(creep, &context.enemy_creeps);
+        Ok(())
+    }
+
+    fn spawn_priority(&self) -> u8 {
+        1
+    }
+
+    fn wanted(&self, _context: &Context) -> u8 {
+        get_strategy().final_test.desired_fighter_count
+    }
+}
+
+struct HealerRole;
+
+impl RoleBehavior for HealerRole {
+    type Context = Context;
+
+    fn name(&self) -> &'static str {
+        HEALER
+    }
+
+    fn body(&self) -> Vec<Part> {
+        vec![Part::Heal, Part::Move, Part::Move]
+    }
+
+    fn run(&self, creep: &Creep, context: &Context) -> Result<()> {
+        let pairings = context.pairings.borrow();
+        let partners = pairings.partners(creep, &context.my_creeps);
+        run_bodyguard(
+            creep,
+            &partners,
+            &context.enemy_creeps,
+            &mut context.traffic.borrow_mut(),
+        )
+    }
+
+    fn spawn_priority(&self) -> u8 {
+        1
+    }
+
+    fn wanted(&self, _context: &Context) -> u8 {
+        get_strategy().final_test.desired_healer_count
     }
 }
 
@@ -57,13 +217,17 @@ enum CreepState {
     Unknown,
 }
 
-impl From<&Creep> for CreepState {
-    fn from(creep: &Creep) -> Self {
-        match get_custom_string_from_creep(creep, "state").as_str() {
-            "Work" => Self::Work,
-            "Harvest" => Self::Harvest,
-            _ => Self::Unknown,
-        }
+impl TryFrom<&Creep> for CreepState {
+    type Error = eyre::Error;
+
+    fn try_from(creep: &Creep) -> Result<Self, Self::Error> {
+        Ok(
+            match get_custom_string_from_creep(creep, "state")?.as_str() {
+                "Work" => Self::Work,
+                "Harvest" => Self::Harvest,
+                _ => Self::Unknown,
+            },
+        )
     }
 }
 
@@ -81,47 +245,95 @@ impl Into<JsValue> for CreepState {
 ///
 /// Roles
 ///     SpawnRefiller
+///     TowerRefiller
 ///     Builder
-///     TurretRefiller
 ///     Fighter
 ///     Ranger
-///     Healer
+///     Healer, follows the fighters and rangers it is paired with
 ///
 /// State
 ///     Work
 ///     Harvest
-#[allow(dead_code)]
-pub fn run(tick: u32) {
-    let spawn = get_objects_by_prototype(prototypes::STRUCTURE_SPAWN)[0].clone();
+fn run(tick: u32, roles: &RoleRegistry<Context>, pairings: &Rc<RefCell<Pairings>>) -> Result<()> {
+    let spawn = get_objects_by_prototype(prototypes::STRUCTURE_SPAWN)
+        .into_iter()
+        .next()
+        .ok_or(eyre::eyre!("Could not find spawn"))?;
 
     if tick == 1 {
         // create_tower_construction_sites(&spawn);
     }
 
     let my_creeps: Vec<Creep> = get_creeps(true);
-    let enemy_creeps = get_creeps(false);
+    let (healers, attackers): (Vec<Creep>, Vec<Creep>) = my_creeps
+        .iter()
+        .filter(|creep| {
+            roles.is_role(creep, HEALER)
+                || roles.is_role(creep, FIGHTER)
+                || roles.is_role(creep, RANGER)
+        })
+        .cloned()
+        .partition(|creep| roles.is_role(creep, HEALER));
+    pairings.borrow_mut().update(&healers, &attackers);
+
+    let context = Context {
+        spawn,
+        my_creeps,
+        enemy_creeps: get_creeps(false),
+        towers: get_objects_by_prototype(prototypes::STRUCTURE_TOWER)
+            .into_iter()
+            .filter(|tower| tower.my().unwrap_or_default())
+            .collect(),
+        pairings: pairings.clone(),
+        traffic: RefCell::new(TrafficManager::new()),
+    };
 
-    let mut role_count = count_roles(&my_creeps);
+    let mut role_count = roles.count_roles(&context.my_creeps);
+    roles.spawn_needed(&context.spawn, &mut role_count, &context)?;
+    roles.run_creeps(&context.my_creeps, &context);
+    TowerController::new((context.spawn.x(), context.spawn.y()), SPAWN_DANGER_RANGE).run(
+        &context.towers,
+        &context.my_creeps,
+        &context.enemy_creeps,
+    );
+    context.traffic.into_inner().resolve();
 
-    if let Some(role_created) = control_spawner(&spawn, &role_count) {
-        increment_role_count(role_created, &mut role_count);
+    if tick % 25 == 0 {
+        log_role_counts(&role_count)
     }
 
-    for creep in &my_creeps {
-        assign_creep_state(creep);
-        match Role::from(creep) {
-            Role::SpawnRefiller => run_spawn_refiller_role(creep, &spawn),
-            Role::Builder => run_builder(creep),
-            Role::TurretRefiller => todo!(),
-            Role::Fighter => run_fighter(creep, &enemy_creeps),
-            Role::Ranger => run_ranger(creep, &enemy_creeps),
-            Role::Healer => todo!(),
-            Role::None => {}
+    Ok(())
+}
+
+pub struct FinalTest {
+    roles: RoleRegistry<Context>,
+    pairings: Rc<RefCell<Pairings>>,
+}
+
+impl FinalTest {
+    pub fn new() -> Self {
+        let roles = RoleRegistry::new()
+            .register(SpawnRefillerRole)
+            .register(TowerRefillerRole)
+            .register(BuilderRole)
+            .register(RangerRole)
+            .register(FighterRole)
+            .register(HealerRole);
+
+        Self {
+            roles,
+            pairings: Rc::new(RefCell::new(Pairings::new())),
         }
     }
+}
 
-    if tick % 25 == 0 {
-        log_role_counts(&role_count)
+impl Arena for FinalTest {
+    fn name(&self) -> &'static str {
+        "tutorial final test"
+    }
+
+    fn tick(&mut self, tick: u32) -> Result<()> {
+        run(tick, &self.roles, &self.pairings)
     }
 }
 
@@ -132,182 +344,176 @@ fn get_creeps(is_my: bool) -> Vec<Creep> {
         .collect()
 }
 
-fn count_roles(creeps: &Vec<Creep>) -> HashMap<Role, u8> {
-    let mut counts = HashMap::new();
-
-    for creep in creeps {
-        let role = Role::from(creep);
-
-        let counts_role = counts.entry(role).or_insert(0);
-        *counts_role += 1;
-    }
-
-    counts
-}
-
-fn get_custom_string_from_creep(creep: &Creep, key: &str) -> String {
+fn get_custom_string_from_creep(creep: &Creep, key: &str) -> Result<String> {
     match Reflect::get(creep, &JsValue::from_str(key)) {
         Ok(value) => {
-            if value.is_string() {
-                value.as_string().unwrap()
+            if let Some(value) = value.as_string() {
+                Ok(value)
             } else {
                 warn!("Error, custom value with key {key} on creep is not a string");
-                String::new()
+                Ok(String::new())
             }
         }
-        Err(error) => {
-            warn!(
-                "Error getting {key} from creep: {}",
-                error.as_string().unwrap_or_default()
-            );
-            panic!();
-        }
+        Err(error) => bail!(
+            "Error getting {key} from creep: {}",
+            error.as_string().unwrap_or_default()
+        ),
     }
 }
 
-fn log_role_counts(role_count: &HashMap<Role, u8>) {
-    warn!("Creep Counts:");
-    warn!("-----");
+fn log_role_counts(role_count: &HashMap<&'static str, u8>) {
+    info!("Creep Counts:");
+    info!("-----");
     for (role, count) in role_count {
-        warn!("role: {:?} - {count}", role);
+        info!("role: {role} - {count}");
     }
-    warn!("-----");
+    info!("-----");
 }
 
-fn control_spawner(spawn: &StructureSpawn, role_count: &HashMap<Role, u8>) -> Option<Role> {
-    if get_creep_count(role_count, Role::SpawnRefiller) < DESIRED_SPAWN_REFILLER_COUNT {
-        return spawn_creep(spawn, Role::SpawnRefiller);
-    }
-
-    if get_creep_count(role_count, Role::Ranger) < DESIRED_RANGER_COUNT {
-        return spawn_creep(spawn, Role::Ranger);
-    }
-
-    if get_creep_count(role_count, Role::Fighter) < DESIRED_FIGHTER_COUNT {
-        return spawn_creep(spawn, Role::Fighter);
-    }
-
-    None
-}
-
-fn get_creep_count(role_count: &HashMap<Role, u8>, role: Role) -> u8 {
-    role_count.get(&role).map_or(0, |count| *count)
-}
-
-fn attach_role_to_creep(role: Role, creep: &Creep) {
-    if let Err(error) = Reflect::set(creep, &JsValue::from_str("role"), &role.into()) {
-        warn!(
-            "Error setting role {:?} on creep {}: {:?}",
-            role,
-            creep.id(),
-            error
-        );
-        panic!();
-    }
-}
-
-fn attach_to_creep(value: JsValue, creep: &Creep, key: &str) {
+fn attach_to_creep(value: JsValue, creep: &Creep, key: &str) -> Result<()> {
     warn!("attaching {key} to creep");
     if let Err(_error) = Reflect::set(creep, &JsValue::from_str(key), &value) {
-        warn!("Error setting {key} {:?} on creep {}", value, creep.id());
-        panic!();
+        bail!("Error setting {key} {:?} on creep {}", value, creep.id());
     }
-}
 
-fn increment_role_count(role: Role, role_count: &mut HashMap<Role, u8>) {
-    let count = role_count.entry(role).or_insert(0);
-    *count += 1;
+    Ok(())
 }
 
-fn spawn_creep(spawn: &StructureSpawn, role: Role) -> Option<Role> {
-    let body = create_creep_body(role);
-    if let Ok(creep) = spawn.spawn_creep(&body) {
-        attach_role_to_creep(role, &creep);
-        Some(role)
-    } else {
-        None
-    }
-}
+fn run_spawn_refiller_role(creep: &Creep, spawn: &StructureSpawn) -> Result<()> {
+    let state = CreepState::try_from(creep)?;
 
-fn create_creep_body(role: Role) -> Vec<Part> {
-    match role {
-        Role::SpawnRefiller => vec![Part::Carry, Part::Work, Part::Move, Part::Move],
-        Role::Builder => vec![Part::Carry, Part::Work, Part::Move, Part::Move],
-        Role::TurretRefiller => vec![Part::Carry, Part::Work, Part::Move, Part::Move],
-        Role::Fighter => vec![Part::Attack, Part::Tough, Part::Move, Part::Move],
-        Role::Ranger => vec![Part::RangedAttack, Part::Tough, Part::Move, Part::Move],
-        Role::Healer => vec![Part::Heal, Part::Move],
-        Role::None => {
-            warn!("Attempting to build creep body with no role");
-            panic!();
+    match state {
+        CreepState::Work => {
+            let energy = creep
+                .store()
+                .get_used_capacity(Some(screeps_arena::ResourceType::Energy));
+            match creep.transfer(spawn, screeps_arena::ResourceType::Energy, None) {
+                ReturnCode::Ok => stats::record_energy_delivered(energy),
+                ReturnCode::NotInRange => {
+                    creep.move_to(spawn, None);
+                }
+                _ => (),
+            }
         }
+        CreepState::Harvest => harvest(creep)?,
+        CreepState::Unknown => bail!("Spawn Refiller in unknown state!"),
     }
-}
 
-fn run_spawn_refiller_role(creep: &Creep, spawn: &StructureSpawn) {
-    let state = CreepState::from(creep);
+    Ok(())
+}
 
-    match state {
+/// Fills the tower that needs it most, a tower with enemies close by before the one with the least
+/// energy. Energy comes from the closest container, or the spawn once the containers are empty.
+fn run_tower_refiller_role(creep: &Creep, context: &Context) -> Result<()> {
+    match CreepState::try_from(creep)? {
         CreepState::Work => {
-            let result = creep.transfer(spawn, screeps_arena::ResourceType::Energy, None);
-            if result == ReturnCode::NotInRange {
-                creep.move_to(spawn, None);
+            let tower = match get_tower_to_refill(&context.towers, &context.enemy_creeps) {
+                Some(tower) => tower,
+                None => return Ok(()),
+            };
+
+            let energy = creep.store().get_used_capacity(Some(ResourceType::Energy));
+            match creep.transfer(tower, ResourceType::Energy, None) {
+                ReturnCode::Ok => stats::record_energy_delivered(energy),
+                ReturnCode::NotInRange => {
+                    creep.move_to(tower, None);
+                }
+                _ => (),
             }
         }
-        CreepState::Harvest => harvest(creep),
-        CreepState::Unknown => {
-            warn!("Spawn Refiller in unknown state!");
-            panic!();
+        CreepState::Harvest => {
+            let free_capacity = creep.store().get_free_capacity(Some(ResourceType::Energy));
+            let containers = get_containers(true);
+            let closest_container =
+                match creep.find_closest_by_path(&containers_to_array(&containers), None) {
+                    Some(closest) => object_to_container(&closest)?,
+                    None => None,
+                };
+
+            let result = match &closest_container {
+                Some(container) => creep.withdraw(container, ResourceType::Energy, None),
+                None => creep.withdraw(&context.spawn, ResourceType::Energy, None),
+            };
+            match result {
+                ReturnCode::Ok => stats::record_energy_withdrawn(free_capacity),
+                ReturnCode::NotInRange => match &closest_container {
+                    Some(container) => {
+                        creep.move_to(container, None);
+                    }
+                    None => {
+                        creep.move_to(&context.spawn, None);
+                    }
+                },
+                _ => (),
+            }
         }
+        CreepState::Unknown => bail!("Tower Refiller in unknown state!"),
     }
+
+    Ok(())
 }
 
-fn assign_creep_state(creep: &Creep) {
-    let state = CreepState::from(creep);
-    let role = Role::from(creep);
+fn get_tower_to_refill<'a>(
+    towers: &'a [StructureTower],
+    enemies: &[Creep],
+) -> Option<&'a StructureTower> {
+    towers
+        .iter()
+        .filter(|tower| needs_energy(tower))
+        .max_by_key(|tower| {
+            let is_under_attack = enemies
+                .iter()
+                .any(|enemy| tower.get_range_to(enemy) <= TOWER_UNDER_ATTACK_RANGE);
+            let energy = tower.store().get_used_capacity(Some(ResourceType::Energy));
+            (is_under_attack, u32::MAX - energy)
+        })
+}
 
-    match role {
-        Role::SpawnRefiller | Role::Builder => match state {
-            CreepState::Work => {
-                if creep.store().get_used_capacity(None) == 0 {
-                    attach_to_creep(CreepState::Harvest.into(), creep, "state");
-                }
+fn needs_energy(tower: &StructureTower) -> bool {
+    tower.store().get_used_capacity(Some(ResourceType::Energy))
+        < get_strategy().final_test.tower_refill_threshold
+}
+
+/// Switches a worker to harvesting once it is empty and back to work once it is full.
+fn assign_harvest_state(creep: &Creep) -> Result<()> {
+    match CreepState::try_from(creep)? {
+        CreepState::Work => {
+            if creep.store().get_used_capacity(None) == 0 {
+                attach_to_creep(CreepState::Harvest.into(), creep, "state")?;
             }
-            CreepState::Harvest => {
-                if creep.store().get_free_capacity(None) == 0 {
-                    attach_to_creep(CreepState::Work.into(), creep, "state");
-                }
+        }
+        CreepState::Harvest => {
+            if creep.store().get_free_capacity(None) == 0 {
+                attach_to_creep(CreepState::Work.into(), creep, "state")?;
             }
-            CreepState::Unknown => attach_to_creep(CreepState::Harvest.into(), creep, "state"),
-        },
-        Role::Fighter => match state {
-            CreepState::Work => {}
-            _ => attach_to_creep(CreepState::Work.into(), creep, "state"),
-        },
-        _ => {}
+        }
+        CreepState::Unknown => attach_to_creep(CreepState::Harvest.into(), creep, "state")?,
     }
+
+    Ok(())
 }
 
-fn harvest(creep: &Creep) {
+fn harvest(creep: &Creep) -> Result<()> {
     let sources = get_objects_by_prototype(prototypes::SOURCE);
     if sources.is_empty() {
-        warn!("Could not find any energy sources");
-        panic!();
+        bail!("Could not find any energy sources");
     }
 
     let result = creep.harvest(&sources[0]);
     if result == ReturnCode::NotInRange {
         creep.move_to(&sources[0], None);
     }
+
+    Ok(())
 }
 
-fn run_builder(creep: &Creep) {
-    let state = CreepState::from(creep);
+fn run_builder(creep: &Creep) -> Result<()> {
+    let state = CreepState::try_from(creep)?;
     match state {
         CreepState::Work => {
             let construction_sites = get_objects_by_prototype(prototypes::CONSTRUCTION_SITE);
             if construction_sites.is_empty() {
-                return;
+                return Ok(());
             }
 
             let result = creep.build(&construction_sites[0]);
@@ -315,9 +521,11 @@ fn run_builder(creep: &Creep) {
                 creep.move_to(&construction_sites[0], None);
             }
         }
-        CreepState::Harvest => harvest(creep),
+        CreepState::Harvest => harvest(creep)?,
         CreepState::Unknown => {}
     }
+
+    Ok(())
 }
 
 fn run_fighter(creep: &Creep, enemies: &Vec<Creep>) {
@@ -339,14 +547,9 @@ fn run_fighter(creep: &Creep, enemies: &Vec<Creep>) {
     }
 }
 
+/// Healers whose heal parts were all destroyed can't heal any more, so they don't count.
 fn is_healer(creep: &Creep) -> bool {
-    for body_part in creep.body() {
-        if body_part.part() == Part::Heal {
-            return true;
-        }
-    }
-
-    false
+    CreepStats::from_creep(creep).heal_per_tick > 0
 }
 
 fn run_ranger(creep: &Creep, enemies: &Vec<Creep>) {
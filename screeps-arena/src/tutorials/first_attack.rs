@@ -1,11 +1,14 @@
+use eyre::Result;
 use screeps_arena::{game::utils::get_objects_by_prototype, prototypes, ReturnCode};
 
+use crate::arena::Arena;
+
 #[allow(dead_code)]
-pub fn run() {
+pub fn run() -> Result<()> {
     let my_creep = get_objects_by_prototype(prototypes::CREEP)
         .into_iter()
         .find(|creep| creep.my())
-        .unwrap();
+        .ok_or(eyre::eyre!("Could not find my creep"))?;
     let enemy = get_objects_by_prototype(prototypes::CREEP)
         .into_iter()
         .find(|creep| !creep.my());
@@ -15,4 +18,18 @@ pub fn run() {
             my_creep.move_to(&enemy, None);
         }
     }
+
+    Ok(())
+}
+
+pub struct FirstAttack;
+
+impl Arena for FirstAttack {
+    fn name(&self) -> &'static str {
+        "tutorial first attack"
+    }
+
+    fn tick(&mut self, _tick: u32) -> Result<()> {
+        run()
+    }
 }
@@ -1,10 +1,12 @@
+use eyre::{bail, Result};
 use js_sys::Reflect;
-use log::warn;
 use screeps_arena::{
     game::utils::get_objects_by_prototype, prototypes, Creep, ReturnCode, Source, StructureSpawn,
 };
 use wasm_bindgen::JsValue;
 
+use crate::arena::Arena;
+
 struct Data {
     pub creep: Creep,
     pub spawn: StructureSpawn,
@@ -12,22 +14,12 @@ struct Data {
 }
 
 impl Data {
-    pub fn init() -> Self {
-        let creeps = get_objects_by_prototype(prototypes::CREEP);
-        let spawns = get_objects_by_prototype(prototypes::STRUCTURE_SPAWN);
-        let energy_sources = get_objects_by_prototype(prototypes::SOURCE);
-
-        Self {
-            creep: creeps[0].clone(),
-            spawn: spawns[0].clone(),
-            energy_source: energy_sources[0].clone(),
-        }
-    }
-
-    pub fn assign_role(&self) {
+    pub fn assign_role(&self) -> Result<()> {
         if let Some(new_role) = self.change_role() {
-            assign_role_to_creep(&self.creep, new_role);
+            assign_role_to_creep(&self.creep, new_role)?;
         }
+
+        Ok(())
     }
 
     fn change_role(&self) -> Option<Role> {
@@ -78,9 +70,8 @@ impl Into<JsValue> for Role {
 }
 
 #[allow(dead_code)]
-pub fn run() {
-    let data = Data::init();
-    data.assign_role();
+fn run(data: &Data) -> Result<()> {
+    data.assign_role()?;
     match get_role_from_creep(&data.creep) {
         Role::Harvest => {
             if data.creep.harvest(&data.energy_source) == ReturnCode::NotInRange {
@@ -98,6 +89,53 @@ pub fn run() {
         }
         Role::None => {}
     }
+
+    Ok(())
+}
+
+pub struct HarvestEnergy {
+    data: Option<Data>,
+}
+
+impl HarvestEnergy {
+    pub fn new() -> Self {
+        Self { data: None }
+    }
+}
+
+impl Arena for HarvestEnergy {
+    fn name(&self) -> &'static str {
+        "tutorial harvest energy"
+    }
+
+    fn init(&mut self) -> Result<()> {
+        let creep = get_objects_by_prototype(prototypes::CREEP)
+            .into_iter()
+            .next()
+            .ok_or(eyre::eyre!("Could not find creep"))?;
+        let spawn = get_objects_by_prototype(prototypes::STRUCTURE_SPAWN)
+            .into_iter()
+            .next()
+            .ok_or(eyre::eyre!("Could not find spawn"))?;
+        let energy_source = get_objects_by_prototype(prototypes::SOURCE)
+            .into_iter()
+            .next()
+            .ok_or(eyre::eyre!("Could not find energy source"))?;
+
+        self.data = Some(Data {
+            creep,
+            spawn,
+            energy_source,
+        });
+        Ok(())
+    }
+
+    fn tick(&mut self, _tick: u32) -> Result<()> {
+        match &self.data {
+            Some(data) => run(data),
+            None => bail!("The tutorial was not initialized"),
+        }
+    }
 }
 
 fn get_role_from_creep(creep: &Creep) -> Role {
@@ -112,11 +150,12 @@ fn get_role_from_creep(creep: &Creep) -> Role {
     }
 }
 
-fn assign_role_to_creep(creep: &Creep, role: Role) {
+fn assign_role_to_creep(creep: &Creep, role: Role) -> Result<()> {
     if let Err(_) = Reflect::set(creep, &js_role_key(), &role.into()) {
-        warn!("Error assigning role to creep");
-        panic!();
+        bail!("Error assigning role to creep");
     }
+
+    Ok(())
 }
 
 fn js_role_key() -> JsValue {
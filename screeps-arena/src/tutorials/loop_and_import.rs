@@ -1,8 +1,25 @@
+use eyre::Result;
 use log::warn;
 use screeps_arena::game;
 
+use crate::arena::Arena;
+
 #[allow(dead_code)]
-pub fn run() {
+pub fn run() -> Result<()> {
     let tick = game::utils::get_ticks();
     warn!("current tick: {tick}");
+
+    Ok(())
+}
+
+pub struct LoopAndImport;
+
+impl Arena for LoopAndImport {
+    fn name(&self) -> &'static str {
+        "tutorial loop and import"
+    }
+
+    fn tick(&mut self, _tick: u32) -> Result<()> {
+        run()
+    }
 }
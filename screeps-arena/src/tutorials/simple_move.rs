@@ -1,9 +1,28 @@
+use eyre::Result;
 use screeps_arena::{game::utils::get_objects_by_prototype, prototypes};
 
+use crate::arena::Arena;
+
 #[allow(dead_code)]
-pub fn run() {
+pub fn run() -> Result<()> {
     let creeps = get_objects_by_prototype(prototypes::CREEP);
     let flags = get_objects_by_prototype(prototypes::FLAG);
 
-    creeps[0].move_to(&flags[0], None);
+    let creep = creeps.first().ok_or(eyre::eyre!("Could not find creep"))?;
+    let flag = flags.first().ok_or(eyre::eyre!("Could not find flag"))?;
+    creep.move_to(flag, None);
+
+    Ok(())
+}
+
+pub struct SimpleMove;
+
+impl Arena for SimpleMove {
+    fn name(&self) -> &'static str {
+        "tutorial simple move"
+    }
+
+    fn tick(&mut self, _tick: u32) -> Result<()> {
+        run()
+    }
 }
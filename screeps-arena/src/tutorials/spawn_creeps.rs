@@ -1,46 +1,76 @@
+use eyre::{bail, Result};
 use js_sys::Reflect;
+use log::warn;
 use screeps_arena::{
     game::utils::get_objects_by_prototype, prototypes, Creep, Flag, Part, StructureSpawn,
 };
 use wasm_bindgen::JsValue;
 
+use crate::arena::Arena;
+
 struct Data {
     pub spawn: StructureSpawn,
     pub flags: Vec<Flag>,
-    pub creeps: Vec<Creep>,
-}
-
-impl Data {
-    pub fn init() -> Self {
-        let spawns = get_objects_by_prototype(prototypes::STRUCTURE_SPAWN);
-        let flags = get_objects_by_prototype(prototypes::FLAG);
-        let creeps = get_objects_by_prototype(prototypes::CREEP);
-
-        Self {
-            spawn: spawns[0].clone(),
-            flags,
-            creeps,
-        }
-    }
 }
 
 #[allow(dead_code)]
-pub fn run() {
-    let data = Data::init();
-    let screeps_count = data.creeps.len();
+fn run(data: &Data) -> Result<()> {
+    let creeps: Vec<Creep> = get_objects_by_prototype(prototypes::CREEP);
+    let screeps_count = creeps.len();
 
     if screeps_count < 2 {
         if let Ok(creep) = data.spawn.spawn_creep(&[Part::Move]) {
             let target_key = JsValue::from_str("target");
-            let flag = &data.flags[screeps_count];
-            Reflect::set(&creep, &target_key, flag).expect("Error setting target on creep");
+            let flag = data.flags.get(screeps_count).ok_or(eyre::eyre!(
+                "Could not find a flag for creep {screeps_count}"
+            ))?;
+            if let Err(error) = Reflect::set(&creep, &target_key, flag) {
+                warn!("Error setting target on creep: {:?}", error);
+            }
         }
     }
 
-    data.creeps.iter().for_each(|creep| {
+    creeps.iter().for_each(|creep| {
         let target_key = JsValue::from_str("target");
         if let Ok(target) = &Reflect::get(creep, &target_key) {
             creep.move_to(target, None);
         }
-    })
+    });
+
+    Ok(())
+}
+
+pub struct SpawnCreeps {
+    data: Option<Data>,
+}
+
+impl SpawnCreeps {
+    pub fn new() -> Self {
+        Self { data: None }
+    }
+}
+
+impl Arena for SpawnCreeps {
+    fn name(&self) -> &'static str {
+        "tutorial spawn creeps"
+    }
+
+    /// The spawn and flags never change, the creeps are looked up every tick as they are spawned.
+    fn init(&mut self) -> Result<()> {
+        let spawn = get_objects_by_prototype(prototypes::STRUCTURE_SPAWN)
+            .into_iter()
+            .next()
+            .ok_or(eyre::eyre!("Could not find spawn"))?;
+        let flags = get_objects_by_prototype(prototypes::FLAG);
+
+        self.data = Some(Data { spawn, flags });
+        Ok(())
+    }
+
+    fn tick(&mut self, _tick: u32) -> Result<()> {
+        match &self.data {
+            Some(data) => run(data),
+            None => bail!("The tutorial was not initialized"),
+        }
+    }
 }
@@ -1,7 +1,13 @@
+use eyre::{bail, Result};
 use screeps_arena::constants::ResourceType;
 use screeps_arena::{game::utils::get_objects_by_prototype, prototypes};
 use screeps_arena::{Creep, ReturnCode, StructureContainer, StructureTower};
 
+use crate::{arena::Arena, global::tower::TowerController};
+
+/// The tutorial's enemy is always worth shooting, wherever it stands.
+const DANGER_RANGE: u8 = 100;
+
 struct Data {
     pub my_creep: Creep,
     pub container: StructureContainer,
@@ -9,8 +15,49 @@ struct Data {
     pub enemy: Creep,
 }
 
-impl Data {
-    pub fn init() -> Self {
+#[allow(dead_code)]
+fn run(data: &Data) -> Result<()> {
+    let result = data
+        .my_creep
+        .withdraw(&data.container, ResourceType::Energy, None);
+
+    if result == ReturnCode::NotInRange {
+        data.my_creep.move_to(&data.container, None);
+    }
+
+    if data
+        .my_creep
+        .transfer(&data.tower, ResourceType::Energy, None)
+        == ReturnCode::NotInRange
+    {
+        data.my_creep.move_to(&data.tower, None);
+    }
+
+    TowerController::new((data.tower.x(), data.tower.y()), DANGER_RANGE).run(
+        std::slice::from_ref(&data.tower),
+        std::slice::from_ref(&data.my_creep),
+        std::slice::from_ref(&data.enemy),
+    );
+
+    Ok(())
+}
+
+pub struct StoreAndTransfer {
+    data: Option<Data>,
+}
+
+impl StoreAndTransfer {
+    pub fn new() -> Self {
+        Self { data: None }
+    }
+}
+
+impl Arena for StoreAndTransfer {
+    fn name(&self) -> &'static str {
+        "tutorial store and transfer"
+    }
+
+    fn init(&mut self) -> Result<()> {
         let mut my_creep = None;
         let mut energy_container = None;
         let mut my_tower = None;
@@ -34,34 +81,19 @@ impl Data {
             .into_iter()
             .for_each(|tower| my_tower = Some(tower));
 
-        Self {
-            my_creep: my_creep.expect("Could not find my creep"),
-            container: energy_container.expect("Could not find container"),
-            tower: my_tower.expect("could not find tower"),
-            enemy: enemy.expect("Could not find enemy creep"),
-        }
-    }
-}
-
-#[allow(dead_code)]
-pub fn run() {
-    let data = Data::init();
-
-    let result = data
-        .my_creep
-        .withdraw(&data.container, ResourceType::Energy, None);
-
-    if result == ReturnCode::NotInRange {
-        data.my_creep.move_to(&data.container, None);
+        self.data = Some(Data {
+            my_creep: my_creep.ok_or(eyre::eyre!("Could not find my creep"))?,
+            container: energy_container.ok_or(eyre::eyre!("Could not find container"))?,
+            tower: my_tower.ok_or(eyre::eyre!("could not find tower"))?,
+            enemy: enemy.ok_or(eyre::eyre!("Could not find enemy creep"))?,
+        });
+        Ok(())
     }
 
-    if data
-        .my_creep
-        .transfer(&data.tower, ResourceType::Energy, None)
-        == ReturnCode::NotInRange
-    {
-        data.my_creep.move_to(&data.tower, None);
+    fn tick(&mut self, _tick: u32) -> Result<()> {
+        match &self.data {
+            Some(data) => run(data),
+            None => bail!("The tutorial was not initialized"),
+        }
     }
-
-    data.tower.attack(&data.enemy);
 }
@@ -1,19 +1,15 @@
+use eyre::{bail, Result};
 use js_sys::Array;
 use screeps_arena::{game::utils::get_objects_by_prototype, prototypes, Creep, Flag};
 
+use crate::arena::Arena;
+
 struct Data {
     creeps: Vec<Creep>,
     flags: Vec<Flag>,
 }
 
 impl Data {
-    pub fn init() -> Self {
-        let creeps = get_objects_by_prototype(prototypes::CREEP);
-        let flags = get_objects_by_prototype(prototypes::FLAG);
-
-        Self { creeps, flags }
-    }
-
     pub fn flags_to_array(&self) -> Array {
         let array = Array::new();
 
@@ -26,12 +22,43 @@ impl Data {
 }
 
 #[allow(dead_code)]
-pub fn run() {
-    let data = Data::init();
-
+fn run(data: &Data) -> Result<()> {
     data.creeps.iter().for_each(|creep| {
         if let Some(flag) = &creep.find_closest_by_path(&data.flags_to_array(), None) {
             creep.move_to(flag, None);
         }
-    })
+    });
+
+    Ok(())
+}
+
+pub struct Terrain {
+    data: Option<Data>,
+}
+
+impl Terrain {
+    pub fn new() -> Self {
+        Self { data: None }
+    }
+}
+
+impl Arena for Terrain {
+    fn name(&self) -> &'static str {
+        "tutorial terrain"
+    }
+
+    fn init(&mut self) -> Result<()> {
+        let creeps = get_objects_by_prototype(prototypes::CREEP);
+        let flags = get_objects_by_prototype(prototypes::FLAG);
+
+        self.data = Some(Data { creeps, flags });
+        Ok(())
+    }
+
+    fn tick(&mut self, _tick: u32) -> Result<()> {
+        match &self.data {
+            Some(data) => run(data),
+            None => bail!("The tutorial was not initialized"),
+        }
+    }
 }